@@ -1,6 +1,8 @@
 use crate::geometry::curves::Curves;
 use crate::geometry::point::Point;
 use crate::duals::DualNumber;
+use crate::expressions::scalar::Scalar;
+use crate::scalar_parametrizations;
 
 use std::f32::consts;
 
@@ -46,18 +48,54 @@ impl<T> CircleArc<T> {
     }
 }
 
+impl CircleArc<f32> {
+    fn x<S: Scalar>(&self, s: S) -> S {
+        let (r, c) = (s.constant(self.r), s.constant(self.c.get_x()));
+        s.cos() * r + c
+    }
+    fn y<S: Scalar>(&self, s: S) -> S {
+        let (r, c) = (s.constant(self.r), s.constant(self.c.get_y()));
+        s.sin() * r + c
+    }
+}
+
 impl Curves<f32> for CircleArc<f32> {
+    scalar_parametrizations!(x, y);
     fn get_smin(&self) -> f32 {
         return self.u
     }
     fn get_smax(&self) -> f32 {
         return self.v
     }
-    fn xs(&self, s: DualNumber<f32>) -> DualNumber<f32> {
-        return  s.cos() * self.r + self.c.get_x()
+    /// Shape parameters are `[r, c_x, c_y, u, v]`.
+    fn get_parameter_count(&self) -> usize {
+        5
+    }
+    fn xs_dp(&self, s: DualNumber<f32>, k: usize) -> DualNumber<f32> {
+        match k {
+            0 => s.cos(),
+            1 => DualNumber::real(1_f32),
+            _ => DualNumber::real(0_f32),
+        }
+    }
+    fn ys_dp(&self, s: DualNumber<f32>, k: usize) -> DualNumber<f32> {
+        match k {
+            0 => s.sin(),
+            2 => DualNumber::real(1_f32),
+            _ => DualNumber::real(0_f32),
+        }
+    }
+    fn smin_dp(&self, k: usize) -> f32 {
+        match k {
+            3 => 1_f32,
+            _ => 0_f32,
+        }
     }
-    fn ys(&self, s: DualNumber<f32>) -> DualNumber<f32> {
-        return s.sin() * self.r + self.c.get_y()
+    fn smax_dp(&self, k: usize) -> f32 {
+        match k {
+            4 => 1_f32,
+            _ => 0_f32,
+        }
     }
 }
 
@@ -70,6 +108,19 @@ fn test_circle_lengths() {
     assert!((half_circle_large.integrate(half_circle_large.v) - 2_f32*consts::PI*10_f32) < delta);
 }
 
+#[test]
+fn test_circle_lengths_with() {
+    use crate::numerical_methods::quadrature::Quadrature;
+
+    let arc = CircleArc::from(3_f32, Point::from(1_f32, 2_f32), 0.5_f32, 2_f32);
+    for rule in [Quadrature::Simpson, Quadrature::GaussKronrod, Quadrature::GaussLegendre(5)] {
+        let result = arc.integrate_with(1.5_f32, rule);
+        assert!(result.converged);
+        assert!((result.value - 3_f32).abs() < 1e-5);
+        assert!(result.error < 1e-5);
+    }
+}
+
 #[test]
 fn test_unit_quadrants() {
     let arcs = [CircleArc::<f32>::unit_quadrant(0), CircleArc::<f32>::unit_quadrant(1), CircleArc::<f32>::unit_quadrant(2), CircleArc::<f32>::unit_quadrant(3)];
@@ -101,6 +152,59 @@ fn test_unit_quadrants() {
     }
 }
 
+#[test]
+fn test_circle_taylor() {
+    let circle = CircleArc::from(2_f32, Point::from(1_f32, -1_f32), 0_f32, consts::PI);
+    let s = 0.4_f32;
+    let xs = circle.taylor_xs(s, 4);
+    let ys = circle.taylor_ys(s, 4);
+    let delta = 1e-6;
+    assert!((xs[0] - (2_f32*s.cos() + 1_f32)).abs() < delta);
+    assert!((xs[1] - circle.dxs(s)).abs() < delta);
+    assert!((ys[1] - circle.dys(s)).abs() < delta);
+    assert!((xs[2] + s.cos()).abs() < delta);
+    assert!((ys[3] + s.cos() / 3_f32).abs() < delta);
+    assert!((xs[4] - s.cos() / 12_f32).abs() < delta);
+}
+
+#[test]
+fn test_circle_bounding_box() {
+    let quadrant = CircleArc::<f32>::unit_quadrant(1);
+    let [x, y] = quadrant.bounding_box(1);
+    let delta = 1e-6;
+    assert!((x.get_lo() + 1_f32).abs() < delta && x.get_hi().abs() < delta);
+    assert!(y.get_lo().abs() < delta && (y.get_hi() - 1_f32).abs() < delta);
+
+    // Finer pieces enclose the sampled curve more tightly
+    let boxes = quadrant.bounding_boxes(8);
+    for k in 0..=80 {
+        let p = quadrant.xy((k as f32) / 80_f32);
+        assert!(boxes.iter().any(|[x, y]| x.contains(p.get_x()) && y.contains(p.get_y())));
+    }
+    assert!(boxes.iter().all(|[x, y]| x.width() < 0.2 && y.width() < 0.2));
+}
+
+#[test]
+fn test_circle_shape_derivatives() {
+    let arc = CircleArc::from(2_f32, Point::from(1_f32, -1_f32), 0.5, 2_f32);
+    let delta = 1e-4;
+    for k in 0..=4 {
+        let t = (k as f32) / 4_f32;
+        let s = 0.5 + 1.5 * t;
+        // Arc length is proportional to the angle, so only u and v move s
+        let radius = arc.xy_dp(t, 0);
+        let start = arc.xy_dp(t, 3);
+        let end = arc.xy_dp(t, 4);
+        assert!(radius.equal(&Point::from(s.cos(), s.sin())));
+        assert!(arc.xy_dp(t, 1).equal(&Point::from(1_f32, 0_f32)));
+        assert!(arc.xy_dp(t, 2).equal(&Point::from(0_f32, 1_f32)));
+        assert!((start.get_x() + 2_f32 * s.sin() * (1_f32 - t)).abs() < delta);
+        assert!((start.get_y() - 2_f32 * s.cos() * (1_f32 - t)).abs() < delta);
+        assert!((end.get_x() + 2_f32 * s.sin() * t).abs() < delta);
+        assert!((end.get_y() - 2_f32 * s.cos() * t).abs() < delta);
+    }
+}
+
 #[test]
 fn test_uncenterd_circles() {
     let c1 = CircleArc::from(2_f32, Point::from(1_f32, -1_f32), 0_f32, 2_f32 * consts::PI);
@@ -0,0 +1,127 @@
+use crate::geometry::curves::Curves;
+use crate::duals::DualNumber;
+use crate::duals::jet::Jet;
+use crate::duals::complex_step;
+
+use num::complex::Complex;
+
+/// Radius of the circle used for the Taylor coefficients of the
+/// parametrizations, which need to be analytic on it
+const TAYLOR_RADIUS: f32 = 0.5;
+
+/// Parametrizations of a `ComplexCurve`
+pub type ComplexParametrization = Box<dyn Fn(Complex<f32>) -> Complex<f32> + Send + Sync>;
+
+/// Curves given by black-box parametrizations that evaluate on complex
+/// numbers, where the derivatives are taken with complex steps.
+pub struct ComplexCurve {
+    x: ComplexParametrization,
+    y: ComplexParametrization,
+
+    s_min: f32,
+    s_max: f32,
+}
+
+impl ComplexCurve {
+    /// Curve with the parametrizations `x` and `y` for `s`
+    /// from `s_min` to `s_max`
+    pub fn from(
+        x: ComplexParametrization,
+        y: ComplexParametrization,
+        s_min: f32,
+        s_max: f32,
+    ) -> ComplexCurve {
+        ComplexCurve{x, y, s_min, s_max}
+    }
+}
+
+impl Curves<f32> for ComplexCurve {
+    fn get_smin(&self) -> f32 {
+        self.s_min
+    }
+    fn get_smax(&self) -> f32 {
+        self.s_max
+    }
+    /// The dual part is carried through with the complex-step derivative.
+    fn xs(&self, s: DualNumber<f32>) -> DualNumber<f32> {
+        DualNumber::from(
+            (self.x)(Complex::new(s.get_a(), 0_f32)).re,
+            s.get_b() * complex_step::diff(&self.x, s.get_a()),
+        )
+    }
+    /// The dual part is carried through with the complex-step derivative.
+    fn ys(&self, s: DualNumber<f32>) -> DualNumber<f32> {
+        DualNumber::from(
+            (self.y)(Complex::new(s.get_a(), 0_f32)).re,
+            s.get_b() * complex_step::diff(&self.y, s.get_a()),
+        )
+    }
+    fn xs_jet(&self, s: Jet<f32>) -> Jet<f32> {
+        complex_step::jet(&self.x, &s, TAYLOR_RADIUS)
+    }
+    fn ys_jet(&self, s: Jet<f32>) -> Jet<f32> {
+        complex_step::jet(&self.y, &s, TAYLOR_RADIUS)
+    }
+    fn xs_complex(&self, s: Complex<f32>) -> Complex<f32> {
+        (self.x)(s)
+    }
+    fn ys_complex(&self, s: Complex<f32>) -> Complex<f32> {
+        (self.y)(s)
+    }
+}
+
+#[test]
+fn test_complex_curve_matches_circle() {
+    use crate::curve_impl::circle_arc::CircleArc;
+    use crate::geometry::curves::Differentiation;
+    use crate::geometry::point::Point;
+
+    let arc = CircleArc::from(2_f32, Point::from(1_f32, -1_f32), 0.3, 2.5);
+    let complex_arc = ComplexCurve::from(
+        Box::new(|s: Complex<f32>| s.cos() * 2_f32 + 1_f32),
+        Box::new(|s: Complex<f32>| s.sin() * 2_f32 - 1_f32),
+        0.3, 2.5,
+    );
+    let delta = 1e-5;
+    for k in 0..=10 {
+        let s = 0.3 + 2.2 * (k as f32) / 10_f32;
+        let t = (k as f32) / 10_f32;
+        assert!((arc.dxs(s) - complex_arc.dxs(s)).abs() < delta);
+        assert!((arc.dys(s) - complex_arc.dys(s)).abs() < delta);
+        assert!((arc.dxs(s) - arc.dxs_with(s, Differentiation::ComplexStep)).abs() < delta);
+        assert!((arc.dys(s) - arc.dys_with(s, Differentiation::ComplexStep)).abs() < delta);
+        assert!(arc.xy(t).equal(&complex_arc.xy(t)));
+    }
+    let taylor = arc.taylor_xs(1_f32, 3);
+    let complex_taylor = complex_arc.taylor_xs(1_f32, 3);
+    for k in 0..=3 {
+        assert!((taylor[k] - complex_taylor[k]).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn test_default_parametrizations() {
+    use crate::geometry::curves::Differentiation;
+    use crate::intervals::Interval;
+
+    // A curve with only the required parametrizations on dual numbers
+    struct Parabola;
+    impl Curves<f32> for Parabola {
+        fn get_smin(&self) -> f32 {
+            0_f32
+        }
+        fn get_smax(&self) -> f32 {
+            1_f32
+        }
+        fn xs(&self, s: DualNumber<f32>) -> DualNumber<f32> {
+            s
+        }
+        fn ys(&self, s: DualNumber<f32>) -> DualNumber<f32> {
+            s * s
+        }
+    }
+    let parabola = Parabola;
+    assert_eq!(parabola.dys_with(0.3, Differentiation::ComplexStep), parabola.dys(0.3));
+    assert_eq!(parabola.taylor_ys(0.3, 1), vec![0.3_f32 * 0.3, 0.6]);
+    assert_eq!(parabola.ys_interval(Interval::from(0_f32, 1_f32)), Interval::entire());
+}
@@ -0,0 +1,98 @@
+use crate::geometry::curves::Curves;
+use crate::expressions::{Expression, ParseError};
+use crate::expressions::scalar::Scalar;
+use crate::scalar_parametrizations;
+
+/// Curves with the parametrizations x(s) and y(s) given as expression
+/// strings in the variable `s`, such as `"2*cos(s)+1"`.
+#[derive(Debug, Clone)]
+pub struct ExpressionCurve {
+    x: Expression,
+    y: Expression,
+
+    s_min: f32,
+    s_max: f32,
+}
+
+impl ExpressionCurve {
+    /// Parses the curve (`x`,`y`) for `s` from `s_min` to `s_max`, where
+    /// the expressions may use the named `parameters`.
+    pub fn parse(x: &str, y: &str, s_min: f32, s_max: f32, parameters: &[(&str, f32)]) -> Result<ExpressionCurve, ParseError> {
+        Ok(ExpressionCurve{
+            x: Expression::parse(x, &["s"], parameters)?,
+            y: Expression::parse(y, &["s"], parameters)?,
+            s_min,
+            s_max,
+        })
+    }
+
+    /// Changes the value of parameter `name` in both parametrizations,
+    /// returns `false` if neither of them has such a parameter
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        let in_x = self.x.set_parameter(name, value);
+        let in_y = self.y.set_parameter(name, value);
+        in_x || in_y
+    }
+}
+
+impl ExpressionCurve {
+    fn x<S: Scalar>(&self, s: S) -> S {
+        self.x.eval(&[s]).expect("Curve expressions take the variable s")
+    }
+    fn y<S: Scalar>(&self, s: S) -> S {
+        self.y.eval(&[s]).expect("Curve expressions take the variable s")
+    }
+}
+
+impl Curves<f32> for ExpressionCurve {
+    scalar_parametrizations!(x, y);
+    fn get_smin(&self) -> f32 {
+        self.s_min
+    }
+    fn get_smax(&self) -> f32 {
+        self.s_max
+    }
+}
+
+#[test]
+fn test_expression_curve_matches_circle() {
+    use crate::curve_impl::circle_arc::CircleArc;
+    use crate::geometry::point::Point;
+
+    let arc = CircleArc::from(2_f32, Point::from(1_f32, -1_f32), 0.3, 2.5);
+    let curve = ExpressionCurve::parse("r*cos(s) + 1", "r*sin(s) - 1", 0.3, 2.5, &[("r", 2_f32)]).unwrap();
+    let delta = 1e-5;
+    for k in 0..=10 {
+        let s = 0.3 + 2.2 * (k as f32) / 10_f32;
+        let t = (k as f32) / 10_f32;
+        assert!((arc.dxs(s) - curve.dxs(s)).abs() < delta);
+        assert!((arc.dys(s) - curve.dys(s)).abs() < delta);
+        assert!(arc.xy(t).equal(&curve.xy(t)));
+    }
+    let [x, y] = curve.bounding_box(4);
+    assert!(x.contains(2_f32*0.3_f32.cos() + 1_f32) && x.contains(2_f32*2.5_f32.cos() + 1_f32));
+    assert!(y.contains(1_f32) && y.get_hi() < 1_f32 + delta);
+}
+
+#[test]
+fn test_expression_grid_function() {
+    use crate::geometry::domain::Domain;
+    use crate::functions::GridFunction;
+    use std::sync::Arc;
+
+    let domain = Arc::new(Domain::new([
+        Box::new(ExpressionCurve::parse("s", "0", 0_f32, 1_f32, &[]).unwrap()),
+        Box::new(ExpressionCurve::parse("1", "s", 0_f32, 1_f32, &[]).unwrap()),
+        Box::new(ExpressionCurve::parse("1-s", "1", 0_f32, 1_f32, &[]).unwrap()),
+        Box::new(ExpressionCurve::parse("0", "1-s", 0_f32, 1_f32, &[]).unwrap()),
+    ], 4, 5));
+    let expression = Expression::parse("x^2 + a*y", &["x", "y"], &[("a", 3_f32)]).unwrap();
+    let fnc = expression.fnc().unwrap();
+    let u = GridFunction::from_fnc(&domain, &fnc);
+    let v = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x*x + 3_f32*y);
+    for i in 0..4 {
+        for j in 0..5 {
+            assert!((u.get_value(i, j) - v.get_value(i, j)).abs() < 1e-6);
+        }
+    }
+}
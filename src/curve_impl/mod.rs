@@ -1,3 +1,5 @@
 pub mod circle_arc;
 pub mod straight_line;
 pub mod special_curve;
+pub mod complex_curve;
+pub mod expression_curve;
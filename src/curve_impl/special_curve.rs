@@ -1,5 +1,10 @@
 use crate::geometry::curves::Curves;
 use crate::duals::DualNumber;
+use crate::duals::jet::Jet;
+use crate::intervals::Interval;
+use crate::expressions::scalar::Scalar;
+
+use num::complex::Complex;
 
 /// A special curve
 pub struct SpecialCurve;
@@ -10,6 +15,18 @@ impl SpecialCurve {
     }
 }
 
+impl SpecialCurve {
+    /// y-value on the branch left of the switch at -3 or on the right one
+    fn y<S: Scalar>(s: S, left: bool) -> S {
+        let (one, half) = (s.constant(1_f32), s.constant(0.5_f32));
+        let exponent = match left {
+            true => (s.clone() + s.constant(6_f32)) * s.constant(-3_f32),
+            false => s.clone() * s.constant(3_f32),
+        };
+        half / (exponent.exp() + one)
+    }
+}
+
 impl<T> Curves<T> for SpecialCurve {
     fn get_smin(&self) -> f32 {
         return -10_f32
@@ -21,9 +38,33 @@ impl<T> Curves<T> for SpecialCurve {
         return s
     }
     fn ys(&self, s: DualNumber<f32>) -> DualNumber<f32> {
-        match s.get_a() < -3_f32 {
-            true => (((s+6_f32)*(-3_f32)).exp() + 1_f32).inv() * 1_f32 / 2_f32,
-            false => ((s*3_f32).exp() + 1_f32).inv() * 1_f32 / 2_f32,
+        SpecialCurve::y(s, s.get_a() < -3_f32)
+    }
+    fn xs_jet(&self, s: Jet<f32>) -> Jet<f32> {
+        s
+    }
+    fn ys_jet(&self, s: Jet<f32>) -> Jet<f32> {
+        let left = s.get_a() < -3_f32;
+        SpecialCurve::y(s, left)
+    }
+    fn xs_interval(&self, s: Interval<f32>) -> Interval<f32> {
+        s
+    }
+    fn ys_interval(&self, s: Interval<f32>) -> Interval<f32> {
+        // Both branches are needed when `s` contains the switch at -3
+        if s.get_hi() < -3_f32 {
+            SpecialCurve::y(s, true)
+        } else if s.get_lo() >= -3_f32 {
+            SpecialCurve::y(s, false)
+        } else {
+            SpecialCurve::y(Interval::from(s.get_lo(), -3_f32), true)
+                .hull(&SpecialCurve::y(Interval::from(-3_f32, s.get_hi()), false))
         }
     }
+    fn xs_complex(&self, s: Complex<f32>) -> Complex<f32> {
+        s
+    }
+    fn ys_complex(&self, s: Complex<f32>) -> Complex<f32> {
+        SpecialCurve::y(s, s.re < -3_f32)
+    }
 }
@@ -1,5 +1,7 @@
 use crate::geometry::curves::Curves;
 use crate::duals::DualNumber;
+use crate::expressions::scalar::Scalar;
+use crate::scalar_parametrizations;
 
 /// Straight lines defined by \[a,b\]*t + \[c,d\]
 /// with t ranging from p_min to p_max,
@@ -78,7 +80,21 @@ impl<T> StraightLine<T> {
     }
 }
 
+impl StraightLine<f32> {
+    /// x-value of line at `t`.
+    fn x<S: Scalar>(&self, t: S) -> S {
+        let (a, c) = (t.constant(self.a), t.constant(self.c));
+        t * a + c
+    }
+    /// y-value of line at `t`.
+    fn y<S: Scalar>(&self, t: S) -> S {
+        let (b, d) = (t.constant(self.b), t.constant(self.d));
+        t * b + d
+    }
+}
+
 impl Curves<f32> for StraightLine<f32> {
+    scalar_parametrizations!(x, y);
     /// Start of curve parametrization.
     fn get_smin(&self) -> f32 {
         return self.s_min
@@ -87,13 +103,25 @@ impl Curves<f32> for StraightLine<f32> {
     fn get_smax(&self) -> f32 {
         return self.s_max
     }
-    /// x-value of line at `s`.
-    fn xs(&self, t: DualNumber<f32>) -> DualNumber<f32> {
-        return t*self.a + self.c
+    /// Shape parameters are the coefficients `[a, b, c, d]`.
+    fn get_parameter_count(&self) -> usize {
+        4
     }
-    /// y-value of line at `s`.
-    fn ys(&self, t: DualNumber<f32>) -> DualNumber<f32> {
-        return t*self.b + self.d
+    /// Derivative of x-value of line at `s` with respect to coefficient `k`.
+    fn xs_dp(&self, t: DualNumber<f32>, k: usize) -> DualNumber<f32> {
+        match k {
+            0 => t,
+            2 => DualNumber::real(1_f32),
+            _ => DualNumber::real(0_f32),
+        }
+    }
+    /// Derivative of y-value of line at `s` with respect to coefficient `k`.
+    fn ys_dp(&self, t: DualNumber<f32>, k: usize) -> DualNumber<f32> {
+        match k {
+            1 => t,
+            3 => DualNumber::real(1_f32),
+            _ => DualNumber::real(0_f32),
+        }
     }
 }
 
@@ -128,4 +156,11 @@ fn test_find_s() {
     assert!((0.3 - x0.find_s(0.3).abs() < 1e-6));
     assert!((0.5 - x0.find_s(0.5).abs() < 1e-6));
     assert!((0.8 - x0.find_s(0.8).abs() < 1e-6));
+
+    use crate::numerical_methods::quadrature::Quadrature;
+    let x1 = StraightLine::<f32>::from(3_f32, 4_f32, 0_f32, 0_f32, -1_f32, 2_f32);
+    for t in [0.1_f32, 0.5, 0.9].iter() {
+        assert!((x1.find_s_with(*t, Quadrature::GaussKronrod) - x1.find_s(*t)).abs() < 1e-5);
+        assert!((x1.find_s_with(*t, Quadrature::GaussLegendre(4)) - (-1_f32 + 3_f32 * t)).abs() < 1e-5);
+    }
 }
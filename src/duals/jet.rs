@@ -0,0 +1,477 @@
+use crate::duals::{DualNumber, GetA};
+
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use std::fmt::Display;
+
+use num::Float;
+
+/// Truncated Taylor series (jets) of arbitrary order.
+///
+/// A jet of order `N` holds the Taylor coefficients `c_k = f⁽ᵏ⁾(x)/k!` for
+/// `k = 0..=N`. Combining jets of different orders gives the lower order. Evaluating a function on `Jet::variable(x, N)` gives all of its
+/// derivatives up to order `N` at `x`, where the order 1 jet is the same as a
+/// `DualNumber`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jet<T> {
+    c: Vec<T>,
+}
+
+impl<T> Jet<T> where T: Float {
+    /// Creates a jet from its Taylor coefficients, the order is one less
+    /// than the amount of coefficients
+    pub fn from(c: Vec<T>) -> Jet<T> {
+        assert!(!c.is_empty(), "A jet needs at least one coefficient");
+        Jet{c}
+    }
+    /// The jet of the independent variable at `x`, with order `order`
+    pub fn variable(x: T, order: usize) -> Jet<T> {
+        let mut c = vec![T::zero(); order + 1];
+        c[0] = x;
+        if order > 0 {
+            c[1] = T::one();
+        }
+        Jet{c}
+    }
+    /// The jet of the constant `x`, with order `order`
+    pub fn constant(x: T, order: usize) -> Jet<T> {
+        let mut c = vec![T::zero(); order + 1];
+        c[0] = x;
+        Jet{c}
+    }
+    /// The order 1 jet with the same value and derivative as `dual`
+    pub fn from_dual(dual: DualNumber<T>) -> Jet<T> {
+        Jet{c: vec![dual.get_a(), dual.get_b()]}
+    }
+    /// Order of the truncation
+    pub fn get_order(&self) -> usize {
+        self.c.len() - 1
+    }
+    /// Returns the value, the zeroth coefficient of the jet
+    pub fn get_a(&self) -> T {
+        self.c[0]
+    }
+    /// Returns the Taylor coefficients of the jet
+    pub fn get_coefficients(&self) -> &[T] {
+        &self.c
+    }
+    /// Returns the `k`th Taylor coefficient, `f⁽ᵏ⁾(x)/k!`
+    pub fn coefficient(&self, k: usize) -> T {
+        self.c[k]
+    }
+    /// Returns the `k`th derivative, `f⁽ᵏ⁾(x)`
+    pub fn derivative(&self, k: usize) -> T {
+        (1..=k).fold(self.c[k], |d, j| d * real(j))
+    }
+    /// Truncates the jet to order `order`
+    pub fn truncate(&self, order: usize) -> Jet<T> {
+        Jet{c: self.c[..=order.min(self.get_order())].to_vec()}
+    }
+    /// The order 1 part of the jet as a dual number
+    pub fn to_dual(&self) -> DualNumber<T> {
+        match self.get_order() {
+            0 => DualNumber::from(self.c[0], T::zero()),
+            _ => DualNumber::from(self.c[0], self.c[1]),
+        }
+    }
+    /// The jet of `g(self)`, where `g` are the Taylor coefficients of `g`
+    /// around the value of the jet
+    pub fn compose(&self, g: &[T]) -> Jet<T> {
+        let mut δ = self.clone();
+        δ.c[0] = T::zero();
+        g.iter().rev().fold(Jet::constant(T::zero(), self.get_order()), |sum, g_k| &(&sum * &δ) + *g_k)
+    }
+
+    /// Inverts the jet (as in doing 1/w)
+    pub fn inv(&self) -> Jet<T> {
+        &Jet::constant(T::one(), self.get_order()) / self
+    }
+    pub fn exp(&self) -> Jet<T> {
+        let mut e = vec![self.c[0].exp(); self.c.len()];
+        for k in 1..self.c.len() {
+            e[k] = (1..=k).fold(T::zero(), |sum, j| sum + real::<T>(j) * self.c[j] * e[k-j]) / real(k);
+        }
+        Jet{c: e}
+    }
+    pub fn ln(&self) -> Jet<T> {
+        let mut l = vec![self.c[0].ln(); self.c.len()];
+        for k in 1..self.c.len() {
+            let sum = (1..k).fold(T::zero(), |sum, j| sum + real::<T>(j) * l[j] * self.c[k-j]);
+            l[k] = (self.c[k] - sum / real(k)) / self.c[0];
+        }
+        Jet{c: l}
+    }
+    pub fn sqrt(&self) -> Jet<T> {
+        let mut s = vec![self.c[0].sqrt(); self.c.len()];
+        for k in 1..self.c.len() {
+            let sum = (1..k).fold(T::zero(), |sum, j| sum + s[j] * s[k-j]);
+            s[k] = (self.c[k] - sum) / (real::<T>(2) * s[0]);
+        }
+        Jet{c: s}
+    }
+    /// Raises the jet to the real power `r`, requires a nonzero value
+    pub fn powf(&self, r: T) -> Jet<T> {
+        let mut p = vec![self.c[0].powf(r); self.c.len()];
+        for k in 1..self.c.len() {
+            let sum = (1..=k).fold(T::zero(), |sum, j| {
+                sum + (r * real(j) - real(k - j)) * self.c[j] * p[k-j]
+            });
+            p[k] = sum / (real::<T>(k) * self.c[0]);
+        }
+        Jet{c: p}
+    }
+    /// Raises the jet to the power `a` by repeated multiplication
+    pub fn pow(&self, a: u8) -> Jet<T> {
+        self.pow_unsigned(a as u32)
+    }
+    /// Raises the jet to the integer power `n`
+    pub fn powi(&self, n: i32) -> Jet<T> {
+        match n < 0 {
+            true => self.inv().pow_unsigned(n.unsigned_abs()),
+            false => self.pow_unsigned(n as u32),
+        }
+    }
+    pub fn sin(&self) -> Jet<T> {
+        self.sin_cos()[0].clone()
+    }
+    pub fn cos(&self) -> Jet<T> {
+        self.sin_cos()[1].clone()
+    }
+    pub fn tan(&self) -> Jet<T> {
+        let [s, c] = self.sin_cos();
+        &s / &c
+    }
+    pub fn sinh(&self) -> Jet<T> {
+        self.sinh_cosh()[0].clone()
+    }
+    pub fn cosh(&self) -> Jet<T> {
+        self.sinh_cosh()[1].clone()
+    }
+    pub fn tanh(&self) -> Jet<T> {
+        let [s, c] = self.sinh_cosh();
+        &s / &c
+    }
+    pub fn asin(&self) -> Jet<T> {
+        let g = (&Jet::constant(T::one(), self.get_order()) - &(self * self)).powf(-real::<T>(1) / real(2));
+        self.antiderivative(self.c[0].asin(), &g)
+    }
+    pub fn acos(&self) -> Jet<T> {
+        let g = -(&Jet::constant(T::one(), self.get_order()) - &(self * self)).powf(-real::<T>(1) / real(2));
+        self.antiderivative(self.c[0].acos(), &g)
+    }
+    pub fn atan(&self) -> Jet<T> {
+        let g = (&(self * self) + T::one()).inv();
+        self.antiderivative(self.c[0].atan(), &g)
+    }
+
+    fn pow_unsigned(&self, a: u32) -> Jet<T> {
+        (0..a).fold(Jet::constant(T::one(), self.get_order()), |p, _| &p * self)
+    }
+
+    /// Sine and cosine of the jet, which are computed together since their
+    /// recurrences depend on each other
+    fn sin_cos(&self) -> [Jet<T>; 2] {
+        let mut s = vec![self.c[0].sin(); self.c.len()];
+        let mut c = vec![self.c[0].cos(); self.c.len()];
+        for k in 1..self.c.len() {
+            let mut s_sum = T::zero();
+            let mut c_sum = T::zero();
+            for j in 1..=k {
+                s_sum = s_sum + real::<T>(j) * self.c[j] * c[k-j];
+                c_sum = c_sum + real::<T>(j) * self.c[j] * s[k-j];
+            }
+            s[k] = s_sum / real(k);
+            c[k] = -c_sum / real(k);
+        }
+        [Jet{c: s}, Jet{c}]
+    }
+
+    fn sinh_cosh(&self) -> [Jet<T>; 2] {
+        let mut s = vec![self.c[0].sinh(); self.c.len()];
+        let mut c = vec![self.c[0].cosh(); self.c.len()];
+        for k in 1..self.c.len() {
+            let mut s_sum = T::zero();
+            let mut c_sum = T::zero();
+            for j in 1..=k {
+                s_sum = s_sum + real::<T>(j) * self.c[j] * c[k-j];
+                c_sum = c_sum + real::<T>(j) * self.c[j] * s[k-j];
+            }
+            s[k] = s_sum / real(k);
+            c[k] = c_sum / real(k);
+        }
+        [Jet{c: s}, Jet{c}]
+    }
+
+    /// The jet of `f(self)` where `f(self(x))` has value `f0` and
+    /// derivative `self'(x) g(x)`
+    fn antiderivative(&self, f0: T, g: &Jet<T>) -> Jet<T> {
+        let mut f = vec![f0; self.c.len()];
+        for (k, f_k) in f.iter_mut().enumerate().skip(1) {
+            *f_k = (1..=k).fold(T::zero(), |sum, j| sum + real::<T>(j) * self.c[j] * g.c[k-j]) / real(k);
+        }
+        Jet{c: f}
+    }
+}
+
+/// Converts `k` into the float type `T`
+fn real<T: Float>(k: usize) -> T {
+    T::from(k).unwrap()
+}
+
+impl GetA for Jet<f32> {
+    fn get_a(&self) -> f32 {
+        self.c[0]
+    }
+}
+
+impl GetA for Jet<f64> {
+    fn get_a(&self) -> f32 {
+        self.c[0] as f32
+    }
+}
+
+impl<T> Display for Jet<T>
+    where T: Display
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.c[0])?;
+        for (k, c) in self.c.iter().enumerate().skip(1) {
+            write!(f, "+{}t^{}", c, k)?;
+        }
+        Ok(())
+    }
+}
+
+/// The coefficients of `a` and `b` combined with `f`, up to the lower of
+/// the two orders, since the higher coefficients of the other jet aren't known
+fn zip_truncated<T: Float>(a: &Jet<T>, b: &Jet<T>, f: impl Fn(T, T) -> T) -> Jet<T> {
+    Jet{c: a.c.iter().zip(b.c.iter()).map(|(a, b)| f(*a, *b)).collect()}
+}
+
+impl<T: Float> Add<&Jet<T>> for &Jet<T> {
+    type Output = Jet<T>;
+    fn add(self, other: &Jet<T>) -> Self::Output {
+        zip_truncated(self, other, |a, b| a + b)
+    }
+}
+
+impl<T: Float> Sub<&Jet<T>> for &Jet<T> {
+    type Output = Jet<T>;
+    fn sub(self, other: &Jet<T>) -> Self::Output {
+        zip_truncated(self, other, |a, b| a - b)
+    }
+}
+
+impl<T: Float> Mul<&Jet<T>> for &Jet<T> {
+    type Output = Jet<T>;
+    fn mul(self, other: &Jet<T>) -> Self::Output {
+        let len = self.c.len().min(other.c.len());
+        let c = (0..len)
+            .map(|k| (0..=k).fold(T::zero(), |sum, j| sum + self.c[j] * other.c[k-j]))
+            .collect();
+        Jet{c}
+    }
+}
+
+impl<T: Float> Div<&Jet<T>> for &Jet<T> {
+    type Output = Jet<T>;
+    fn div(self, other: &Jet<T>) -> Self::Output {
+        let len = self.c.len().min(other.c.len());
+        let mut q = vec![T::zero(); len];
+        for k in 0..len {
+            let sum = (0..k).fold(T::zero(), |sum, j| sum + q[j] * other.c[k-j]);
+            q[k] = (self.c[k] - sum) / other.c[0];
+        }
+        Jet{c: q}
+    }
+}
+
+impl<T: Float> Neg for &Jet<T> {
+    type Output = Jet<T>;
+    fn neg(self) -> Self::Output {
+        Jet{c: self.c.iter().map(|a| -*a).collect()}
+    }
+}
+
+impl<T: Float> Add for Jet<T> {
+    type Output = Jet<T>;
+    fn add(self, other: Jet<T>) -> Self::Output {
+        &self + &other
+    }
+}
+
+impl<T: Float> Sub for Jet<T> {
+    type Output = Jet<T>;
+    fn sub(self, other: Jet<T>) -> Self::Output {
+        &self - &other
+    }
+}
+
+impl<T: Float> Mul for Jet<T> {
+    type Output = Jet<T>;
+    fn mul(self, other: Jet<T>) -> Self::Output {
+        &self * &other
+    }
+}
+
+impl<T: Float> Div for Jet<T> {
+    type Output = Jet<T>;
+    fn div(self, other: Jet<T>) -> Self::Output {
+        &self / &other
+    }
+}
+
+impl<T: Float> Neg for Jet<T> {
+    type Output = Jet<T>;
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+impl<T: Float> Add<T> for &Jet<T> {
+    type Output = Jet<T>;
+    fn add(self, other: T) -> Self::Output {
+        let mut c = self.c.clone();
+        c[0] = c[0] + other;
+        Jet{c}
+    }
+}
+
+impl<T: Float> Sub<T> for &Jet<T> {
+    type Output = Jet<T>;
+    fn sub(self, other: T) -> Self::Output {
+        let mut c = self.c.clone();
+        c[0] = c[0] - other;
+        Jet{c}
+    }
+}
+
+impl<T: Float> Mul<T> for &Jet<T> {
+    type Output = Jet<T>;
+    fn mul(self, other: T) -> Self::Output {
+        Jet{c: self.c.iter().map(|a| *a * other).collect()}
+    }
+}
+
+impl<T: Float> Div<T> for &Jet<T> {
+    type Output = Jet<T>;
+    fn div(self, other: T) -> Self::Output {
+        Jet{c: self.c.iter().map(|a| *a / other).collect()}
+    }
+}
+
+impl<T: Float> Add<T> for Jet<T> {
+    type Output = Jet<T>;
+    fn add(self, other: T) -> Self::Output {
+        &self + other
+    }
+}
+
+impl<T: Float> Sub<T> for Jet<T> {
+    type Output = Jet<T>;
+    fn sub(self, other: T) -> Self::Output {
+        &self - other
+    }
+}
+
+impl<T: Float> Mul<T> for Jet<T> {
+    type Output = Jet<T>;
+    fn mul(self, other: T) -> Self::Output {
+        &self * other
+    }
+}
+
+impl<T: Float> Div<T> for Jet<T> {
+    type Output = Jet<T>;
+    fn div(self, other: T) -> Self::Output {
+        &self / other
+    }
+}
+
+/// Returns the Taylor coefficients up to order `order` of `f` around `x`
+pub fn taylor<T>(f: &dyn Fn(Jet<T>) -> Jet<T>, x: T, order: usize) -> Vec<T>
+    where T: Float
+{
+    f(Jet::variable(x, order)).c
+}
+
+/// Returns the `k`th derivative of `f` at `x`
+pub fn diff_n<T>(f: &dyn Fn(Jet<T>) -> Jet<T>, x: T, k: usize) -> T
+    where T: Float
+{
+    f(Jet::variable(x, k)).derivative(k)
+}
+
+#[test]
+fn test_jet_derivatives() {
+    let f = |x: Jet<f64>| -> Jet<f64> { x.pow(5) + x.pow(2) };
+    assert_eq!(diff_n(&f, 2.0, 1), 84.0);
+    assert_eq!(diff_n(&f, 2.0, 2), 162.0);
+    assert_eq!(diff_n(&f, 2.0, 5), 120.0);
+    assert_eq!(diff_n(&f, 2.0, 6), 0.0);
+
+    // Every derivative of exp is exp, and sin cycles with period 4
+    let delta = 1e-12;
+    let e = taylor(&|x: Jet<f64>| x.exp(), 0.0, 6);
+    let s = Jet::variable(0.3_f64, 8).sin();
+    for k in 0..=6 {
+        assert!((e[k] * (1..=k).product::<usize>() as f64 - 1.0).abs() < delta);
+    }
+    for k in 0..=4 {
+        assert!((s.derivative(k) - s.derivative(k + 4)).abs() < delta);
+    }
+}
+
+#[test]
+fn test_jet_compositions() {
+    // Compositions that reduce to simple functions
+    let x = Jet::variable(0.4_f64, 7);
+    let delta = 1e-10;
+    let identities = [
+        (x.ln().exp(), x.clone()),
+        (x.sqrt() * x.sqrt(), x.clone()),
+        (x.powf(1.5), &x * &x.sqrt()),
+        (x.tan(), &x.sin() / &x.cos()),
+        (x.atan().tan(), x.clone()),
+        (x.asin().sin(), x.clone()),
+        (x.acos().cos(), x.clone()),
+        (&(x.cosh() * x.cosh()) - &(x.sinh() * x.sinh()), Jet::constant(1.0, 7)),
+        (x.tanh(), &x.sinh() / &x.cosh()),
+        (x.inv() * x.clone(), Jet::constant(1.0, 7)),
+    ];
+    for (lhs, rhs) in identities.iter() {
+        for k in 0..=7 {
+            assert!((lhs.coefficient(k) - rhs.coefficient(k)).abs() < delta);
+        }
+    }
+}
+
+#[test]
+fn test_jet_dual_special_case() {
+    let dual = DualNumber::from(0.7_f64, 1.0);
+    let jet = Jet::from_dual(dual);
+    let dual_result = (dual.sin() * dual.exp()).inv();
+    let jet_result = (jet.sin() * jet.exp()).inv();
+    assert!((jet_result.to_dual().get_a() - dual_result.get_a()).abs() < 1e-12);
+    assert!((jet_result.to_dual().get_b() - dual_result.get_b()).abs() < 1e-12);
+
+    // Every elementary function of the order 1 jet is the dual number one
+    let functions: [(fn(&DualNumber<f64>) -> DualNumber<f64>, fn(&Jet<f64>) -> Jet<f64>); 14] = [
+        (|d| d.sin(), |j| j.sin()), (|d| d.cos(), |j| j.cos()), (|d| d.tan(), |j| j.tan()),
+        (|d| d.exp(), |j| j.exp()), (|d| d.ln(), |j| j.ln()), (|d| d.sqrt(), |j| j.sqrt()),
+        (|d| d.sinh(), |j| j.sinh()), (|d| d.cosh(), |j| j.cosh()), (|d| d.tanh(), |j| j.tanh()),
+        (|d| d.atan(), |j| j.atan()), (|d| d.inv(), |j| j.inv()), (|d| d.powf(1.5), |j| j.powf(1.5)),
+        (|d| d.powi(-2), |j| j.powi(-2)), (|d| d.pow(0), |j| j.pow(0)),
+    ];
+    for (f_dual, f_jet) in functions.iter() {
+        let (d, j) = (f_dual(&dual), f_jet(&jet).to_dual());
+        assert!((d.get_a() - j.get_a()).abs() < 1e-12 && (d.get_b() - j.get_b()).abs() < 1e-12);
+    }
+    assert_eq!((dual.pow(0).get_a(), dual.pow(0).get_b()), (1_f64, 0_f64));
+
+    // Every operator truncates to the lower order
+    let cubic = Jet::variable(0.7_f64, 3);
+    assert_eq!((&cubic + &jet).get_coefficients(), &[1.4, 2.0]);
+    for result in [&jet - &cubic, &cubic * &jet, &jet / &cubic] {
+        assert_eq!(result.get_order(), 1);
+    }
+}
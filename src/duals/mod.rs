@@ -1,5 +1,8 @@
 extern crate num;
 
+pub mod jet;
+pub mod complex_step;
+
 use std::ops::{Add, Sub, Mul, Div, Neg};
 use std::fmt::Display;
 
@@ -11,6 +14,9 @@ use num::{One, Zero};
 /// This gives rise to the ability to perform automatic differentiation
 /// of analytical functions using the taylor expansion where terms
 /// of order >= 2 will be 0 due to the nature of dual numbers.
+///
+/// They compute the same as the order 1 `Jet`, see `Jet::from_dual`, but
+/// are `Copy` and don't allocate, which the curve evaluations rely on.
 #[derive(Debug,Copy,Clone)]
 pub struct DualNumber<T> {
     a: T,
@@ -95,9 +101,65 @@ impl DualNumber<f32> {
             b: self.b * self.a.exp(),
         }
     }
+    pub fn tan(&self) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.tan(),
+            b: self.b / (self.a.cos() * self.a.cos()),
+        }
+    }
+    pub fn ln(&self) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.ln(),
+            b: self.b / self.a,
+        }
+    }
+    pub fn sqrt(&self) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.sqrt(),
+            b: self.b / (2_f32 * self.a.sqrt()),
+        }
+    }
+    pub fn sinh(&self) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.sinh(),
+            b: self.b * self.a.cosh(),
+        }
+    }
+    pub fn cosh(&self) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.cosh(),
+            b: self.b * self.a.sinh(),
+        }
+    }
+    pub fn tanh(&self) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.tanh(),
+            b: self.b * (1_f32 - self.a.tanh() * self.a.tanh()),
+        }
+    }
+    pub fn atan(&self) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.atan(),
+            b: self.b / (1_f32 + self.a * self.a),
+        }
+    }
+    /// Raises the dual number to the real power `r`
+    pub fn powf(&self, r: f32) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.powf(r),
+            b: self.b * r * self.a.powf(r - 1_f32),
+        }
+    }
+    /// Raises the dual number to the integer power `n`
+    pub fn powi(&self, n: i32) -> DualNumber<f32> {
+        DualNumber {
+            a: self.a.powi(n),
+            b: self.b * (n as f32) * self.a.powi(n - 1),
+        }
+    }
     pub fn pow(&self, a: u8) -> DualNumber<f32> {
         match a == 0 {
-            true => return DualNumber::from(0.0, 0.0),
+            true => return DualNumber::real(1_f32),
             false => (),
         }
         let mut dual = DualNumber::from(self.a, self.b);
@@ -127,9 +189,65 @@ impl DualNumber<f64> {
             b: self.b * self.a.exp(),
         }
     }
+    pub fn tan(&self) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.tan(),
+            b: self.b / (self.a.cos() * self.a.cos()),
+        }
+    }
+    pub fn ln(&self) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.ln(),
+            b: self.b / self.a,
+        }
+    }
+    pub fn sqrt(&self) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.sqrt(),
+            b: self.b / (2_f64 * self.a.sqrt()),
+        }
+    }
+    pub fn sinh(&self) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.sinh(),
+            b: self.b * self.a.cosh(),
+        }
+    }
+    pub fn cosh(&self) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.cosh(),
+            b: self.b * self.a.sinh(),
+        }
+    }
+    pub fn tanh(&self) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.tanh(),
+            b: self.b * (1_f64 - self.a.tanh() * self.a.tanh()),
+        }
+    }
+    pub fn atan(&self) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.atan(),
+            b: self.b / (1_f64 + self.a * self.a),
+        }
+    }
+    /// Raises the dual number to the real power `r`
+    pub fn powf(&self, r: f64) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.powf(r),
+            b: self.b * r * self.a.powf(r - 1_f64),
+        }
+    }
+    /// Raises the dual number to the integer power `n`
+    pub fn powi(&self, n: i32) -> DualNumber<f64> {
+        DualNumber {
+            a: self.a.powi(n),
+            b: self.b * (n as f64) * self.a.powi(n - 1),
+        }
+    }
     pub fn pow(&self, a: u8) -> DualNumber<f64> {
         match a == 0 {
-            true => return DualNumber::from(0_f64, 0_f64),
+            true => return DualNumber::from(1_f64, 0_f64),
             false => (),
         }
         let mut dual = DualNumber::from(self.a, self.b);
@@ -141,6 +259,18 @@ impl DualNumber<f64> {
     }
 }
 
+impl<T> Neg for DualNumber<T>
+    where T: Neg<Output=T>
+{
+    type Output = DualNumber<T>;
+    fn neg(self) -> Self::Output {
+        DualNumber {
+            a: -self.a,
+            b: -self.b,
+        }
+    }
+}
+
 impl<T> Display for DualNumber<T> 
     where T: Display
 {
@@ -226,7 +356,7 @@ impl<T> Div<DualNumber<T>> for DualNumber<T>
     fn div(self, other: DualNumber<T>) -> Self::Output {
         DualNumber {
             a: self.a / other.a,
-            b: -self.a * other.b / (self.a*self.a) + self.b / other.a,
+            b: -self.a * other.b / (other.a*other.a) + self.b / other.a,
         }
     }
 }
@@ -290,7 +420,7 @@ impl<'a, T> Div<&'a DualNumber<T>> for &'a DualNumber<T>
     fn div(self, other: &'a DualNumber<T>) -> Self::Output {
         DualNumber {
             a: &self.a / &other.a,
-            b: -(&self.a * &other.b) / (&self.a * &self.a) + &self.b / &other.a,
+            b: -(&self.a * &other.b) / (&other.a * &other.a) + &self.b / &other.a,
         }
     }
 }
@@ -312,3 +442,17 @@ fn test_differentiation_powers() {
     assert_eq!(diff(&f1, 5.0), 10.0);
     assert_eq!(diff(&f2, 2.0), 84.0);
 }
+
+#[test]
+fn test_differentiation_quotient() {
+    // d/dx x/(x²+1) = (1-x²)/(x²+1)², where the denominator of the quotient
+    // rule is the square of the divisor
+    let f1 = |x: DualNumber<f32>| -> DualNumber<f32> {
+        x / (x * x + 1_f32)
+    };
+    let f2 = |x: DualNumber<f32>| -> DualNumber<f32> {
+        &x / &(x * x + 1_f32)
+    };
+    assert!((diff(&f1, 2.0) + 0.12).abs() < 1e-6);
+    assert!((diff(&f2, 2.0) + 0.12).abs() < 1e-6);
+}
@@ -0,0 +1,193 @@
+pub mod scalar;
+mod parser;
+
+use crate::expressions::parser::{Parser, Node, Function};
+use crate::expressions::scalar::Scalar;
+
+use std::fmt::Display;
+
+/// Errors from parsing an expression, with the character position
+/// in the source where they occur
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    UnexpectedCharacter(usize, char),
+    UnexpectedToken(usize, String),
+    UnexpectedEnd,
+    InvalidNumber(usize, String),
+    UnknownIdentifier(usize, String),
+    UnknownFunction(usize, String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedCharacter(p, c) => write!(f, "Unexpected character '{}' at {}", c, p),
+            ParseError::UnexpectedToken(p, t) => write!(f, "Unexpected '{}' at {}", t, p),
+            ParseError::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            ParseError::InvalidNumber(p, t) => write!(f, "Invalid number '{}' at {}", t, p),
+            ParseError::UnknownIdentifier(p, t) => write!(f, "Unknown variable or parameter '{}' at {}", t, p),
+            ParseError::UnknownFunction(p, t) => write!(f, "Unknown function '{}' at {}", t, p),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Mathematical expressions parsed from strings such as `"2*cos(s)+1"`.
+///
+/// Expressions consist of numbers, `+ - * / ^`, parentheses, the functions
+/// `sin cos tan exp ln sqrt sinh cosh tanh atan`, the constants `pi` and `e`,
+/// and the variables and named parameters they are parsed with. They can be
+/// evaluated on any `Scalar`, so for instance on `DualNumber` to get exact
+/// derivatives.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    root: Node,
+    variables: Vec<String>,
+    parameters: Vec<(String, f32)>,
+}
+
+impl Expression {
+    /// Parses `source` where the names in `variables` are the arguments the
+    /// expression is evaluated with and `parameters` are named constants.
+    pub fn parse(source: &str, variables: &[&str], parameters: &[(&str, f32)]) -> Result<Expression, ParseError> {
+        let parameter_names: Vec<&str> = parameters.iter().map(|(name, _)| *name).collect();
+        let root = Parser::parse(source, variables, &parameter_names)?;
+        Ok(Expression{
+            root,
+            variables: variables.iter().map(|v| v.to_string()).collect(),
+            parameters: parameters.iter().map(|(name, value)| (name.to_string(), *value)).collect(),
+        })
+    }
+
+    /// Names of the variables, in the order `eval` takes them
+    pub fn get_variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Names and values of the parameters
+    pub fn get_parameters(&self) -> &[(String, f32)] {
+        &self.parameters
+    }
+
+    /// Changes the value of parameter `name`, returns `false` if the
+    /// expression has no such parameter
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        match self.parameters.iter_mut().find(|(p, _)| p == name) {
+            Some(parameter) => {
+                parameter.1 = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Evaluates the expression with `variables` in the order the variable
+    /// names were given when parsing. The constants take the shape of the
+    /// first variable, or `Scalar::real` without variables. Returns `None`
+    /// for the wrong number of variables.
+    pub fn eval<S: Scalar>(&self, variables: &[S]) -> Option<S> {
+        if variables.len() != self.variables.len() {
+            return None
+        }
+        match variables.first() {
+            Some(template) => Some(self.eval_node(&self.root, variables, template)),
+            None => Some(self.eval_node(&self.root, variables, &S::real(0_f32))),
+        }
+    }
+
+    /// The expression of the two variables `(x,y)` as a closure, for
+    /// instance to create a `GridFunction` with `from_fnc`. Returns `None`
+    /// if the expression doesn't take exactly two variables.
+    pub fn fnc(&self) -> Option<impl Fn(f32, f32) -> f32 + '_> {
+        match self.variables.len() {
+            2 => Some(move |x: f32, y: f32| -> f32 { self.eval(&[x, y]).expect("The expression takes two variables") }),
+            _ => None,
+        }
+    }
+
+    fn eval_node<S: Scalar>(&self, node: &Node, variables: &[S], template: &S) -> S {
+        let eval = |node: &Node| -> S { self.eval_node(node, variables, template) };
+        match node {
+            Node::Number(x) => template.constant(*x),
+            Node::Variable(k) => variables[*k].clone(),
+            Node::Parameter(k) => template.constant(self.parameters[*k].1),
+            Node::Neg(a) => -eval(a),
+            Node::Add(a, b) => eval(a) + eval(b),
+            Node::Sub(a, b) => eval(a) - eval(b),
+            Node::Mul(a, b) => eval(a) * eval(b),
+            Node::Div(a, b) => eval(a) / eval(b),
+            Node::Pow(a, b) => match b.has_variables() {
+                true => (eval(b) * eval(a).ln()).exp(),
+                false => {
+                    // Constant exponents keep negative bases working for integers
+                    let r = self.eval_node(b, &[], &0_f32);
+                    match r.fract() == 0_f32 && r.abs() <= i32::MAX as f32 {
+                        true => eval(a).powi(r as i32),
+                        false => eval(a).powf(r),
+                    }
+                }
+            },
+            Node::Call(function, a) => {
+                let a = eval(a);
+                match function {
+                    Function::Sin => a.sin(),
+                    Function::Cos => a.cos(),
+                    Function::Tan => a.tan(),
+                    Function::Exp => a.exp(),
+                    Function::Ln => a.ln(),
+                    Function::Sqrt => a.sqrt(),
+                    Function::Sinh => a.sinh(),
+                    Function::Cosh => a.cosh(),
+                    Function::Tanh => a.tanh(),
+                    Function::Atan => a.atan(),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_expression_values() {
+    use crate::duals::DualNumber;
+
+    let expression = Expression::parse("r*cos(s)^2 + (s - 1)^3 / 2 - sqrt(exp(s)) + 2^s", &["s"], &[("r", 2_f32)]).unwrap();
+    let f = |s: f32, r: f32| -> f32 { r*s.cos().powi(2) + (s - 1_f32).powi(3) / 2_f32 - s.exp().sqrt() + 2_f32.powf(s) };
+    for s in [-0.5_f32, 0.3, 1.7].iter() {
+        assert!((expression.eval(&[*s]).unwrap() - f(*s, 2_f32)).abs() < 1e-5);
+    }
+    let mut expression = expression;
+    assert!(expression.set_parameter("r", -1_f32));
+    assert!(!expression.set_parameter("q", 0_f32));
+    assert!((expression.eval(&[0.3_f32]).unwrap() - f(0.3, -1_f32)).abs() < 1e-5);
+    assert!(expression.fnc().is_none());
+    assert!(expression.eval(&[0.3_f32, 1_f32]).is_none() && expression.eval::<f32>(&[]).is_none());
+
+    // Without variables the constants get their shape from `Scalar::real`
+    let constant = Expression::parse("2*a + sin(pi/2)", &[], &[("a", 1.5_f32)]).unwrap();
+    assert!((constant.eval::<f32>(&[]).unwrap() - 4_f32).abs() < 1e-6);
+    assert_eq!(constant.eval::<DualNumber<f32>>(&[]).unwrap().get_b(), 0_f32);
+}
+
+#[test]
+fn test_expression_derivatives() {
+    use crate::duals::{self, DualNumber};
+
+    let expression = Expression::parse("tanh(s)*atan(s)/(1+s^2) + ln(2+sin(s))", &["s"], &[]).unwrap();
+    let f = |s: DualNumber<f32>| -> DualNumber<f32> { expression.eval(&[s]).unwrap() };
+    let f_exact = |s: DualNumber<f32>| -> DualNumber<f32> {
+        s.tanh() * s.atan() / (s.pow(2) + 1_f32) + (s.sin() + 2_f32).ln()
+    };
+    for s in [-1.2_f32, 0_f32, 0.7].iter() {
+        assert!((duals::diff(&f, *s) - duals::diff(&f_exact, *s)).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_expression_errors() {
+    assert_eq!(Expression::parse("2*cos(s", &["s"], &[]).unwrap_err(), ParseError::UnexpectedEnd);
+    assert_eq!(Expression::parse("2*q", &["s"], &[]).unwrap_err(), ParseError::UnknownIdentifier(2, "q".to_string()));
+    assert_eq!(Expression::parse("cot(s)", &["s"], &[]).unwrap_err(), ParseError::UnknownFunction(0, "cot".to_string()));
+    assert_eq!(Expression::parse("s $ 2", &["s"], &[]).unwrap_err(), ParseError::UnexpectedCharacter(2, '$'));
+    assert_eq!(Expression::parse("s 2", &["s"], &[]).unwrap_err(), ParseError::UnexpectedToken(2, "2".to_string()));
+}
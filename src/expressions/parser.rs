@@ -0,0 +1,264 @@
+use crate::expressions::ParseError;
+
+/// Elementary functions that can be called in expressions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Function {
+    Sin, Cos, Tan, Exp, Ln, Sqrt, Sinh, Cosh, Tanh, Atan,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            "tan" => Some(Function::Tan),
+            "exp" => Some(Function::Exp),
+            "ln" | "log" => Some(Function::Ln),
+            "sqrt" => Some(Function::Sqrt),
+            "sinh" => Some(Function::Sinh),
+            "cosh" => Some(Function::Cosh),
+            "tanh" => Some(Function::Tanh),
+            "atan" => Some(Function::Atan),
+            _ => None,
+        }
+    }
+}
+
+/// Syntax tree of a parsed expression, where variables and parameters
+/// are indices into the names the expression was parsed with
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Number(f32),
+    Variable(usize),
+    Parameter(usize),
+    Neg(Box<Node>),
+    Add(Box<Node>, Box<Node>),
+    Sub(Box<Node>, Box<Node>),
+    Mul(Box<Node>, Box<Node>),
+    Div(Box<Node>, Box<Node>),
+    Pow(Box<Node>, Box<Node>),
+    Call(Function, Box<Node>),
+}
+
+impl Node {
+    /// Does the value of the node depend on any of the variables?
+    pub(crate) fn has_variables(&self) -> bool {
+        match self {
+            Node::Number(_) | Node::Parameter(_) => false,
+            Node::Variable(_) => true,
+            Node::Neg(a) | Node::Call(_, a) => a.has_variables(),
+            Node::Add(a, b) | Node::Sub(a, b) | Node::Mul(a, b) | Node::Div(a, b) | Node::Pow(a, b) => {
+                a.has_variables() || b.has_variables()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Identifier(String),
+    Operator(char),
+}
+
+/// Splits `source` into tokens together with their positions
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponent, only when digits follow so that 2e means 2*e
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let sign = usize::from(i + 1 < chars.len() && (chars[i+1] == '+' || chars[i+1] == '-'));
+                if i + 1 + sign < chars.len() && chars[i + 1 + sign].is_ascii_digit() {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            match text.parse::<f32>() {
+                Ok(x) => tokens.push((start, Token::Number(x))),
+                Err(_) => return Err(ParseError::InvalidNumber(start, text)),
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Identifier(chars[start..i].iter().collect())));
+        } else if "+-*/^()".contains(c) {
+            tokens.push((start, Token::Operator(c)));
+            i += 1;
+        } else {
+            return Err(ParseError::UnexpectedCharacter(start, c));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser for the grammar
+///
+/// ```text
+/// sum     = product (("+" | "-") product)*
+/// product = unary (("*" | "/") unary)*
+/// unary   = ("-" | "+") unary | power
+/// power   = primary ("^" unary)?
+/// primary = number | name | name "(" sum ")" | "(" sum ")"
+/// ```
+pub(crate) struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    variables: &'a [&'a str],
+    parameters: &'a [&'a str],
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn parse(source: &str, variables: &'a [&'a str], parameters: &'a [&'a str]) -> Result<Node, ParseError> {
+        let mut parser = Parser{tokens: tokenize(source)?, position: 0, variables, parameters};
+        let node = parser.sum()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(node),
+            Some((position, token)) => Err(ParseError::UnexpectedToken(*position, token_text(token))),
+        }
+    }
+
+    fn peek_operator(&self) -> Option<char> {
+        match self.tokens.get(self.position) {
+            Some((_, Token::Operator(c))) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn expect_operator(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.tokens.get(self.position) {
+            Some((_, Token::Operator(c))) if *c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some((position, token)) => Err(ParseError::UnexpectedToken(*position, token_text(token))),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Node, ParseError> {
+        let mut node = self.product()?;
+        while let Some(c) = self.peek_operator() {
+            match c {
+                '+' => { self.position += 1; node = Node::Add(Box::new(node), Box::new(self.product()?)); }
+                '-' => { self.position += 1; node = Node::Sub(Box::new(node), Box::new(self.product()?)); }
+                _ => break,
+            }
+        }
+        Ok(node)
+    }
+
+    fn product(&mut self) -> Result<Node, ParseError> {
+        let mut node = self.unary()?;
+        while let Some(c) = self.peek_operator() {
+            match c {
+                '*' => { self.position += 1; node = Node::Mul(Box::new(node), Box::new(self.unary()?)); }
+                '/' => { self.position += 1; node = Node::Div(Box::new(node), Box::new(self.unary()?)); }
+                _ => break,
+            }
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        match self.peek_operator() {
+            Some('-') => {
+                self.position += 1;
+                Ok(Node::Neg(Box::new(self.unary()?)))
+            }
+            Some('+') => {
+                self.position += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Node, ParseError> {
+        let base = self.primary()?;
+        match self.peek_operator() {
+            Some('^') => {
+                self.position += 1;
+                Ok(Node::Pow(Box::new(base), Box::new(self.unary()?)))
+            }
+            _ => Ok(base),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, ParseError> {
+        let (position, token) = match self.tokens.get(self.position) {
+            Some(token) => token.clone(),
+            None => return Err(ParseError::UnexpectedEnd),
+        };
+        self.position += 1;
+        match token {
+            Token::Number(x) => Ok(Node::Number(x)),
+            Token::Operator('(') => {
+                let node = self.sum()?;
+                self.expect_operator(')')?;
+                Ok(node)
+            }
+            Token::Operator(c) => Err(ParseError::UnexpectedToken(position, c.to_string())),
+            Token::Identifier(name) => {
+                if self.peek_operator() == Some('(') {
+                    let function = Function::from_name(&name)
+                        .ok_or(ParseError::UnknownFunction(position, name))?;
+                    self.position += 1;
+                    let argument = self.sum()?;
+                    self.expect_operator(')')?;
+                    return Ok(Node::Call(function, Box::new(argument)))
+                }
+                if let Some(k) = self.variables.iter().position(|v| *v == name) {
+                    Ok(Node::Variable(k))
+                } else if let Some(k) = self.parameters.iter().position(|p| *p == name) {
+                    Ok(Node::Parameter(k))
+                } else {
+                    match name.as_str() {
+                        "pi" => Ok(Node::Number(std::f32::consts::PI)),
+                        "e" => Ok(Node::Number(std::f32::consts::E)),
+                        _ => Err(ParseError::UnknownIdentifier(position, name)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Number(x) => x.to_string(),
+        Token::Identifier(name) => name.clone(),
+        Token::Operator(c) => c.to_string(),
+    }
+}
+
+#[test]
+fn test_parse_precedence() {
+    let x = || Box::new(Node::Variable(0));
+    let number = |a: f32| Box::new(Node::Number(a));
+    assert_eq!(
+        Parser::parse("-x^2 + 3*x/2", &["x"], &[]),
+        Ok(Node::Add(
+            Box::new(Node::Neg(Box::new(Node::Pow(x(), number(2_f32))))),
+            Box::new(Node::Div(Box::new(Node::Mul(number(3_f32), x())), number(2_f32))),
+        ))
+    );
+    assert_eq!(
+        Parser::parse("2^-x", &["x"], &[]),
+        Ok(Node::Pow(number(2_f32), Box::new(Node::Neg(x()))))
+    );
+    assert_eq!(Parser::parse("1.5e-1*e", &[], &[]), Ok(Node::Mul(number(0.15), number(std::f32::consts::E))));
+}
@@ -0,0 +1,257 @@
+use crate::duals::DualNumber;
+use crate::duals::jet::Jet;
+use crate::intervals::Interval;
+
+use num::complex::Complex;
+
+use std::ops::{Add, Sub, Mul, Div, Neg};
+
+/// Numbers that expressions can be evaluated on, which are the number
+/// types the curve parametrizations are evaluated on.
+pub trait Scalar: Clone
+    + Add<Output=Self> + Sub<Output=Self> + Mul<Output=Self> + Div<Output=Self> + Neg<Output=Self>
+{
+    /// The constant `c` in the same shape as `self`, such as a jet
+    /// of the same order
+    fn constant(&self, c: f32) -> Self;
+    /// The constant `c` in the simplest shape, such as a jet of order 0,
+    /// for when there is nothing to take the shape from
+    fn real(c: f32) -> Self;
+    fn sin(&self) -> Self;
+    fn cos(&self) -> Self;
+    fn tan(&self) -> Self;
+    fn exp(&self) -> Self;
+    fn ln(&self) -> Self;
+    fn sqrt(&self) -> Self;
+    fn sinh(&self) -> Self;
+    fn cosh(&self) -> Self;
+    fn tanh(&self) -> Self;
+    fn atan(&self) -> Self;
+    fn powi(&self, n: i32) -> Self;
+    fn powf(&self, r: f32) -> Self;
+}
+
+impl Scalar for f32 {
+    fn constant(&self, c: f32) -> f32 {
+        c
+    }
+    fn real(c: f32) -> f32 {
+        c
+    }
+    fn sin(&self) -> f32 {
+        (*self).sin()
+    }
+    fn cos(&self) -> f32 {
+        (*self).cos()
+    }
+    fn tan(&self) -> f32 {
+        (*self).tan()
+    }
+    fn exp(&self) -> f32 {
+        (*self).exp()
+    }
+    fn ln(&self) -> f32 {
+        (*self).ln()
+    }
+    fn sqrt(&self) -> f32 {
+        (*self).sqrt()
+    }
+    fn sinh(&self) -> f32 {
+        (*self).sinh()
+    }
+    fn cosh(&self) -> f32 {
+        (*self).cosh()
+    }
+    fn tanh(&self) -> f32 {
+        (*self).tanh()
+    }
+    fn atan(&self) -> f32 {
+        (*self).atan()
+    }
+    fn powi(&self, n: i32) -> f32 {
+        (*self).powi(n)
+    }
+    fn powf(&self, r: f32) -> f32 {
+        (*self).powf(r)
+    }
+}
+
+impl Scalar for DualNumber<f32> {
+    fn constant(&self, c: f32) -> DualNumber<f32> {
+        DualNumber::real(c)
+    }
+    fn real(c: f32) -> DualNumber<f32> {
+        DualNumber::real(c)
+    }
+    fn sin(&self) -> DualNumber<f32> {
+        self.sin()
+    }
+    fn cos(&self) -> DualNumber<f32> {
+        self.cos()
+    }
+    fn tan(&self) -> DualNumber<f32> {
+        self.tan()
+    }
+    fn exp(&self) -> DualNumber<f32> {
+        self.exp()
+    }
+    fn ln(&self) -> DualNumber<f32> {
+        self.ln()
+    }
+    fn sqrt(&self) -> DualNumber<f32> {
+        self.sqrt()
+    }
+    fn sinh(&self) -> DualNumber<f32> {
+        self.sinh()
+    }
+    fn cosh(&self) -> DualNumber<f32> {
+        self.cosh()
+    }
+    fn tanh(&self) -> DualNumber<f32> {
+        self.tanh()
+    }
+    fn atan(&self) -> DualNumber<f32> {
+        self.atan()
+    }
+    fn powi(&self, n: i32) -> DualNumber<f32> {
+        self.powi(n)
+    }
+    fn powf(&self, r: f32) -> DualNumber<f32> {
+        self.powf(r)
+    }
+}
+
+impl Scalar for Jet<f32> {
+    fn constant(&self, c: f32) -> Jet<f32> {
+        Jet::constant(c, self.get_order())
+    }
+    fn real(c: f32) -> Jet<f32> {
+        Jet::constant(c, 0)
+    }
+    fn sin(&self) -> Jet<f32> {
+        self.sin()
+    }
+    fn cos(&self) -> Jet<f32> {
+        self.cos()
+    }
+    fn tan(&self) -> Jet<f32> {
+        self.tan()
+    }
+    fn exp(&self) -> Jet<f32> {
+        self.exp()
+    }
+    fn ln(&self) -> Jet<f32> {
+        self.ln()
+    }
+    fn sqrt(&self) -> Jet<f32> {
+        self.sqrt()
+    }
+    fn sinh(&self) -> Jet<f32> {
+        self.sinh()
+    }
+    fn cosh(&self) -> Jet<f32> {
+        self.cosh()
+    }
+    fn tanh(&self) -> Jet<f32> {
+        self.tanh()
+    }
+    fn atan(&self) -> Jet<f32> {
+        self.atan()
+    }
+    fn powi(&self, n: i32) -> Jet<f32> {
+        self.powi(n)
+    }
+    fn powf(&self, r: f32) -> Jet<f32> {
+        self.powf(r)
+    }
+}
+
+impl Scalar for Interval<f32> {
+    fn constant(&self, c: f32) -> Interval<f32> {
+        Interval::point(c)
+    }
+    fn real(c: f32) -> Interval<f32> {
+        Interval::point(c)
+    }
+    fn sin(&self) -> Interval<f32> {
+        self.sin()
+    }
+    fn cos(&self) -> Interval<f32> {
+        self.cos()
+    }
+    fn tan(&self) -> Interval<f32> {
+        self.tan()
+    }
+    fn exp(&self) -> Interval<f32> {
+        self.exp()
+    }
+    fn ln(&self) -> Interval<f32> {
+        self.ln()
+    }
+    fn sqrt(&self) -> Interval<f32> {
+        self.sqrt()
+    }
+    fn sinh(&self) -> Interval<f32> {
+        self.sinh()
+    }
+    fn cosh(&self) -> Interval<f32> {
+        self.cosh()
+    }
+    fn tanh(&self) -> Interval<f32> {
+        self.tanh()
+    }
+    fn atan(&self) -> Interval<f32> {
+        self.atan()
+    }
+    fn powi(&self, n: i32) -> Interval<f32> {
+        self.powi(n)
+    }
+    fn powf(&self, r: f32) -> Interval<f32> {
+        self.powf(r)
+    }
+}
+
+impl Scalar for Complex<f32> {
+    fn constant(&self, c: f32) -> Complex<f32> {
+        Complex::new(c, 0_f32)
+    }
+    fn real(c: f32) -> Complex<f32> {
+        Complex::new(c, 0_f32)
+    }
+    fn sin(&self) -> Complex<f32> {
+        (*self).sin()
+    }
+    fn cos(&self) -> Complex<f32> {
+        (*self).cos()
+    }
+    fn tan(&self) -> Complex<f32> {
+        (*self).tan()
+    }
+    fn exp(&self) -> Complex<f32> {
+        (*self).exp()
+    }
+    fn ln(&self) -> Complex<f32> {
+        (*self).ln()
+    }
+    fn sqrt(&self) -> Complex<f32> {
+        (*self).sqrt()
+    }
+    fn sinh(&self) -> Complex<f32> {
+        (*self).sinh()
+    }
+    fn cosh(&self) -> Complex<f32> {
+        (*self).cosh()
+    }
+    fn tanh(&self) -> Complex<f32> {
+        (*self).tanh()
+    }
+    fn atan(&self) -> Complex<f32> {
+        (*self).atan()
+    }
+    fn powi(&self, n: i32) -> Complex<f32> {
+        self.powi(n)
+    }
+    fn powf(&self, r: f32) -> Complex<f32> {
+        (*self).powf(r)
+    }
+}
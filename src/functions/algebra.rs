@@ -0,0 +1,181 @@
+//! Arithmetic on grid functions, pointwise on the values.
+//!
+//! Operators between two functions check that they share their domain and
+//! return `Result<GridFunction, DomainMismatch>`. The assigning operators
+//! only take scalars; in place arithmetic with another function goes through
+//! `try_add_assign` and friends, which return the mismatch as well.
+use crate::functions::{GridFunction, DomainMismatch};
+
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use std::ops::{AddAssign, SubAssign, MulAssign, DivAssign};
+
+/// Implements `$trait` between owned and borrowed functions
+macro_rules! function_operator {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<GridFunction<f32>> for GridFunction<f32> {
+            type Output = Result<GridFunction<f32>, DomainMismatch>;
+            fn $method(self, other: GridFunction<f32>) -> Self::Output {
+                self.zip_map(&other, &|u, v| u $op v)
+            }
+        }
+
+        impl $trait<&GridFunction<f32>> for GridFunction<f32> {
+            type Output = Result<GridFunction<f32>, DomainMismatch>;
+            fn $method(self, other: &GridFunction<f32>) -> Self::Output {
+                self.zip_map(other, &|u, v| u $op v)
+            }
+        }
+
+        impl $trait<GridFunction<f32>> for &GridFunction<f32> {
+            type Output = Result<GridFunction<f32>, DomainMismatch>;
+            fn $method(self, other: GridFunction<f32>) -> Self::Output {
+                self.zip_map(&other, &|u, v| u $op v)
+            }
+        }
+
+        impl $trait<&GridFunction<f32>> for &GridFunction<f32> {
+            type Output = Result<GridFunction<f32>, DomainMismatch>;
+            fn $method(self, other: &GridFunction<f32>) -> Self::Output {
+                self.zip_map(other, &|u, v| u $op v)
+            }
+        }
+    };
+}
+
+/// Implements `$trait` with a scalar on the right
+macro_rules! scalar_operator {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<f32> for GridFunction<f32> {
+            type Output = GridFunction<f32>;
+            fn $method(self, scalar: f32) -> Self::Output {
+                self.map(&|u| u $op scalar)
+            }
+        }
+
+        impl $trait<f32> for &GridFunction<f32> {
+            type Output = GridFunction<f32>;
+            fn $method(self, scalar: f32) -> Self::Output {
+                self.map(&|u| u $op scalar)
+            }
+        }
+    };
+}
+
+/// Implements `$trait` with a scalar on the left
+macro_rules! left_scalar_operator {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<GridFunction<f32>> for f32 {
+            type Output = GridFunction<f32>;
+            fn $method(self, function: GridFunction<f32>) -> Self::Output {
+                function.map(&|u| self $op u)
+            }
+        }
+
+        impl $trait<&GridFunction<f32>> for f32 {
+            type Output = GridFunction<f32>;
+            fn $method(self, function: &GridFunction<f32>) -> Self::Output {
+                function.map(&|u| self $op u)
+            }
+        }
+    };
+}
+
+/// Implements `$trait` with a scalar
+macro_rules! assign_operator {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<f32> for GridFunction<f32> {
+            fn $method(&mut self, scalar: f32) {
+                self.values.iter_mut().for_each(|u| *u $op scalar);
+            }
+        }
+    };
+}
+
+/// Implements the in place `$method` with another function, which leaves
+/// `self` unchanged if the domains don't match
+macro_rules! try_assign_operator {
+    ($method:ident, $op:tt, $doc:literal) => {
+        impl GridFunction<f32> {
+            #[doc = $doc]
+            pub fn $method(&mut self, other: &GridFunction<f32>) -> Result<(), DomainMismatch> {
+                self.check_domain(other)?;
+                self.values.iter_mut().zip(other.values.iter()).for_each(|(u, v)| *u $op *v);
+                Ok(())
+            }
+        }
+    };
+}
+
+function_operator!(Add, add, +);
+function_operator!(Sub, sub, -);
+function_operator!(Mul, mul, *);
+function_operator!(Div, div, /);
+
+scalar_operator!(Add, add, +);
+scalar_operator!(Sub, sub, -);
+scalar_operator!(Mul, mul, *);
+scalar_operator!(Div, div, /);
+
+left_scalar_operator!(Add, add, +);
+left_scalar_operator!(Sub, sub, -);
+left_scalar_operator!(Mul, mul, *);
+left_scalar_operator!(Div, div, /);
+
+assign_operator!(AddAssign, add_assign, +=);
+assign_operator!(SubAssign, sub_assign, -=);
+assign_operator!(MulAssign, mul_assign, *=);
+assign_operator!(DivAssign, div_assign, /=);
+
+try_assign_operator!(try_add_assign, +=, "Adds `other` in place");
+try_assign_operator!(try_sub_assign, -=, "Subtracts `other` in place");
+try_assign_operator!(try_mul_assign, *=, "Multiplies by `other` in place");
+try_assign_operator!(try_div_assign, /=, "Divides by `other` in place");
+
+impl Neg for GridFunction<f32> {
+    type Output = GridFunction<f32>;
+    fn neg(self) -> Self::Output {
+        self.map(&|u| -u)
+    }
+}
+
+impl Neg for &GridFunction<f32> {
+    type Output = GridFunction<f32>;
+    fn neg(self) -> Self::Output {
+        self.map(&|u| -u)
+    }
+}
+
+#[test]
+fn test_grid_function_algebra() {
+    use crate::geometry::domain::Domain;
+    use std::sync::Arc;
+
+    let domain = Arc::new(Domain::unit_square(4, 3));
+    let u = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x + y + 1_f32);
+    let f = GridFunction::from_fnc(&domain, &|x: f32, _: f32| 2_f32 * x);
+
+    // 2(u - f)/u + 1 - u*f, with owned and borrowed operands
+    let mut residual = 2_f32 * (&u - &f).unwrap();
+    residual.try_div_assign(&u).unwrap();
+    residual += 1_f32;
+    residual.try_sub_assign(&(&u * f.clone()).unwrap()).unwrap();
+    let quotient = ((-&f + u.clone()).unwrap() * 2_f32 / u.clone()).unwrap();
+    let expected = ((1_f32 + quotient) - (u.clone() * &f).unwrap()).unwrap();
+    let zipped = u.zip_map(&f, &|u, f| 2_f32 * (u - f) / u + 1_f32 - u * f).unwrap();
+    for i in 0..4 {
+        for j in 0..3 {
+            assert!((residual.get_value(i, j) - expected.get_value(i, j)).abs() < 1e-5);
+            assert!((residual.get_value(i, j) - zipped.get_value(i, j)).abs() < 1e-5);
+        }
+    }
+    assert_eq!(u.map(&|u| u * u).get_value(3, 2), 9_f32);
+    // The zero function is shaped differently internally, but indexed the same
+    assert_eq!((GridFunction::new(&domain) + &u).unwrap().get_value(3, 1), u.get_value(3, 1));
+
+    let other = GridFunction::from_fnc(&Arc::new(Domain::unit_square(4, 3)), &|x: f32, _: f32| x);
+    assert_eq!((&u + &other).err(), Some(DomainMismatch));
+    assert_eq!(u.check_domain(&other), Err(DomainMismatch));
+    let mut unchanged = u.clone();
+    assert_eq!(unchanged.try_add_assign(&other), Err(DomainMismatch));
+    assert_eq!(unchanged.get_value(3, 1), u.get_value(3, 1));
+}
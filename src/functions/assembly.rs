@@ -0,0 +1,90 @@
+//! The differential operators of `GridFunction` assembled as sparse matrices
+//! acting on the values flattened as `GridFunction::to_vector`, for implicit
+//! solves, eigenvalue analysis or export. `GridFunction::pdx`, `pdy` and
+//! `laplace` apply these matrices, so both give exactly the same values.
+use crate::functions::GridFunction;
+use crate::geometry::domain::Domain;
+use crate::numerical_methods::finite_differences::{DifferenceOrder, stencil};
+use crate::numerical_methods::sparse::CsrMatrix;
+
+use std::sync::Arc;
+
+/// The matrix of `GridFunction::pdx` on `domain` with the stencils of `order`
+pub fn pdx_matrix(domain: &Arc<Domain<f32>>, order: DifferenceOrder) -> CsrMatrix {
+    partial_derivative_matrix(domain, order, 0)
+}
+
+/// The matrix of `GridFunction::pdy` on `domain` with the stencils of `order`
+pub fn pdy_matrix(domain: &Arc<Domain<f32>>, order: DifferenceOrder) -> CsrMatrix {
+    partial_derivative_matrix(domain, order, 1)
+}
+
+/// The matrix of `GridFunction::laplace`, D_x D_x + D_y D_y
+pub fn laplace_matrix(domain: &Arc<Domain<f32>>, order: DifferenceOrder) -> CsrMatrix {
+    let (d_x, d_y) = (pdx_matrix(domain, order), pdy_matrix(domain, order));
+    d_x.mul_matrix(&d_x).add_matrix(&d_y.mul_matrix(&d_y))
+}
+
+/// The chain rule u_x = ξ_x u_ξ + η_x u_η, or the same for y with
+/// `component` 1, with the reference derivatives of `line_derivative`
+fn partial_derivative_matrix(domain: &Arc<Domain<f32>>, order: DifferenceOrder, component: usize) -> CsrMatrix {
+    let (n, m) = (domain.get_n() as usize, domain.get_m() as usize);
+    let (h_ξ, h_η) = (domain.get_h_ξ(), domain.get_h_η());
+    let mut triplets = Vec::new();
+    for i in 0..n {
+        for j in 0..m {
+            let row = i * m + j;
+            let [ξ_x, ξ_y, η_x, η_y] = domain.get_metric_of_order(i, j, order).inverse();
+            let (ξ_d, η_d) = match component {
+                0 => (ξ_x, η_x),
+                _ => (ξ_y, η_y),
+            };
+            let (start, weights) = stencil(1, order, i, n - 1);
+            for (l, w) in weights.iter().enumerate().filter(|(_, w)| **w != 0_f32) {
+                triplets.push((row, (start + l) * m + j, w / h_ξ * ξ_d));
+            }
+            let (start, weights) = stencil(1, order, j, m - 1);
+            for (l, w) in weights.iter().enumerate().filter(|(_, w)| **w != 0_f32) {
+                triplets.push((row, i * m + start + l, w / h_η * η_d));
+            }
+        }
+    }
+    CsrMatrix::from_triplets(n * m, n * m, &triplets)
+}
+
+impl GridFunction<f32> {
+    /// The function with the values of `operator` applied to the values of
+    /// this function
+    pub fn apply(&self, operator: &CsrMatrix) -> GridFunction<f32> {
+        GridFunction::from_vector(&self.domain, operator.mul_vector(&self.to_vector())).with_order(self.order)
+    }
+}
+
+#[test]
+fn test_assembled_operators_match_grid_functions() {
+    let domain = Arc::new(Domain::quarter_annulus(15, 11));
+    for order in DifferenceOrder::ALL {
+        let u = GridFunction::from_fnc(&domain, &|x, y| x.sin() * y * y).with_order(order);
+        assert_eq!(u.apply(&pdx_matrix(&domain, order)).to_vector(), u.pdx().to_vector());
+        assert_eq!(u.apply(&pdy_matrix(&domain, order)).to_vector(), u.pdy().to_vector());
+        assert_eq!(u.apply(&laplace_matrix(&domain, order)).to_vector(), u.laplace().to_vector());
+        let grad = u.grad();
+        assert_eq!(grad.get_x().to_vector(), u.pdx().to_vector());
+        assert_eq!(grad.get_y().to_vector(), u.pdy().to_vector());
+    }
+
+    let location = std::env::temp_dir().join("numerical_grids_laplace.mtx");
+    let location = location.to_str().unwrap();
+    let laplace = laplace_matrix(&domain, DifferenceOrder::Second);
+    laplace.save_matrix_market(location).unwrap();
+    let text = std::fs::read_to_string(location).unwrap();
+    std::fs::remove_file(location).unwrap();
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("%%MatrixMarket matrix coordinate real general"));
+    assert_eq!(lines.next(), Some(format!("165 165 {}", laplace.get_nnz()).as_str()));
+    let entries: Vec<(usize, usize, f32)> = lines.map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        (fields[0].parse::<usize>().unwrap() - 1, fields[1].parse::<usize>().unwrap() - 1, fields[2].parse().unwrap())
+    }).collect();
+    assert_eq!(CsrMatrix::from_triplets(165, 165, &entries), laplace);
+}
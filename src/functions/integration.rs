@@ -0,0 +1,162 @@
+use crate::functions::GridFunction;
+
+/// Quadrature rules along the grid lines in reference coordinates
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GridQuadrature {
+    Trapezoid,
+    /// Composite Simpson, closed with Simpson's 3/8 rule on the last three
+    /// intervals when the number of intervals is odd
+    Simpson,
+}
+
+/// Weights of `rule` for `points` equidistant points with spacing `h`
+fn weights(rule: GridQuadrature, points: usize, h: f32) -> Vec<f32> {
+    let intervals = points - 1;
+    let mut w = vec![0_f32; points];
+    if rule == GridQuadrature::Trapezoid || intervals < 2 {
+        for k in 0..intervals {
+            w[k] += h / 2_f32;
+            w[k+1] += h / 2_f32;
+        }
+        return w
+    }
+    let simpson_intervals = match intervals % 2 {
+        0 => intervals,
+        _ => intervals - 3,
+    };
+    for k in (0..simpson_intervals).step_by(2) {
+        w[k] += h / 3_f32;
+        w[k+1] += 4_f32 * h / 3_f32;
+        w[k+2] += h / 3_f32;
+    }
+    if simpson_intervals < intervals {
+        let k = simpson_intervals;
+        for (l, c) in [1_f32, 3_f32, 3_f32, 1_f32].iter().enumerate() {
+            w[k+l] += 3_f32 * h * c / 8_f32;
+        }
+    }
+    w
+}
+
+impl GridFunction<f32> {
+    /// Calculates ∫∫ u dx dy over the domain, as the integral of u|J| over
+    /// the reference square where J is the Jacobian of the grid mapping
+    pub fn integrate(&self, rule: GridQuadrature) -> f32 {
+        self.integrate_values(rule, &|u: f32| u)
+    }
+
+    /// The total ∫∫ u dx dy, integrated with Simpson's rule
+    pub fn mass(&self) -> f32 {
+        self.integrate(GridQuadrature::Simpson)
+    }
+
+    /// The energy ½∫∫ u² dx dy, integrated with Simpson's rule
+    pub fn energy(&self) -> f32 {
+        self.integrate_values(GridQuadrature::Simpson, &|u: f32| u * u / 2_f32)
+    }
+
+    /// Calculates ∫ u ds along boundary side `side`, numbered as the curves
+    /// of the domain
+    pub fn line_integral(&self, side: usize, rule: GridQuadrature) -> f32 {
+        self.side_integral(side, rule, &|u: f32, x_t: f32, y_t: f32| -> [f32; 2] {
+            [u * (x_t * x_t + y_t * y_t).sqrt(), 0_f32]
+        })[0]
+    }
+
+    /// Calculates ∫ u n ds along boundary side `side`, where n is the outward
+    /// unit normal. The flux of a vector field (p,q) through the side is the
+    /// first component for p plus the second component for q.
+    pub fn normal_line_integral(&self, side: usize, rule: GridQuadrature) -> [f32; 2] {
+        let sign = self.domain.outward_sign(side);
+        self.side_integral(side, rule, &|u: f32, x_t: f32, y_t: f32| -> [f32; 2] {
+            [sign * u * y_t, -sign * u * x_t]
+        })
+    }
+
+    pub(super) fn integrate_values(&self, rule: GridQuadrature, f: &dyn Fn(f32) -> f32) -> f32 {
+        let w_ξ = weights(rule, self.n as usize, self.h_ξ);
+        let w_η = weights(rule, self.m as usize, self.h_η);
+        let mut integral = 0_f32;
+        for (i, w_i) in w_ξ.iter().enumerate() {
+            for (j, w_j) in w_η.iter().enumerate() {
+                integral += w_i * w_j * f(self.get_value(i, j)) * self.jacobian(i, j).abs();
+            }
+        }
+        integral
+    }
+
+    /// Integrates `f(u, x_t, y_t)` along side `side`, where t is the
+    /// reference coordinate running along the side, over the gridpoints
+    /// in the order of `Domain::side_indices`
+    fn side_integral(&self, side: usize, rule: GridQuadrature, f: &dyn Fn(f32, f32, f32) -> [f32; 2]) -> [f32; 2] {
+        let indices = self.domain.side_indices(side);
+        let h = match side {
+            0 | 2 => self.h_ξ,
+            _ => self.h_η,
+        };
+        let w = weights(rule, indices.len(), h);
+        let mut integral = [0_f32; 2];
+        for ((i, j), w_k) in indices.iter().copied().zip(w.iter()) {
+            let metric = self.domain.get_metric(i, j);
+            let (x_t, y_t) = match side {
+                0 | 2 => (metric.x_ξ, metric.y_ξ),
+                _ => (metric.x_η, metric.y_η),
+            };
+            let value = f(self.get_value(i, j), x_t, y_t);
+            integral[0] += w_k * value[0];
+            integral[1] += w_k * value[1];
+        }
+        integral
+    }
+
+    /// Determinant of the Jacobian of the grid mapping at index i,j
+    fn jacobian(&self, i: usize, j: usize) -> f32 {
+        self.domain.get_metric(i, j).jacobian
+    }
+}
+
+#[test]
+fn test_integrate_unit_square() {
+    use crate::geometry::domain::Domain;
+    use std::sync::Arc;
+
+    let delta = 1e-5;
+
+    let domain = Arc::new(Domain::unit_square(3, 3));
+    let bilinear = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x * y);
+    assert!((bilinear.integrate(GridQuadrature::Trapezoid) - 0.25).abs() < delta);
+
+    // Simpson is exact for cubics, also with the 3/8 closure
+    for (n, m) in [(5, 5), (6, 6), (4, 7)] {
+        let domain = Arc::new(Domain::unit_square(n, m));
+        let cubic = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x * x * y * y * y);
+        assert!((cubic.integrate(GridQuadrature::Simpson) - 1_f32 / 12_f32).abs() < delta);
+        assert!((cubic.energy() - 1_f32 / 70_f32).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn test_integrate_annulus() {
+    use crate::geometry::domain::Domain;
+    use std::sync::Arc;
+    use std::f32::consts::PI;
+
+    // Quarter of the annulus 1 < r < 2
+    let domain = Arc::new(Domain::quarter_annulus(21, 21));
+    let one = GridFunction::from_fnc(&domain, &|_: f32, _: f32| 1_f32);
+    let x = GridFunction::from_fnc(&domain, &|x: f32, _: f32| x);
+    let delta = 1e-2;
+    assert!((one.mass() - 3_f32 * PI / 4_f32).abs() < delta);
+    assert!((x.integrate(GridQuadrature::Trapezoid) - 7_f32 / 3_f32).abs() < delta);
+
+    assert!((one.line_integral(0, GridQuadrature::Simpson) - 1_f32).abs() < delta);
+    assert!((one.line_integral(1, GridQuadrature::Simpson) - PI).abs() < delta);
+    assert!((one.line_integral(3, GridQuadrature::Simpson) - PI / 2_f32).abs() < delta);
+
+    // Divergence theorem for the field (x,0)
+    let flux: f32 = (0..4).map(|side| x.normal_line_integral(side, GridQuadrature::Simpson)[0]).sum();
+    assert!((flux - 3_f32 * PI / 4_f32).abs() < delta);
+    // The outer arc has normal (x,y)/2
+    let [n_x, n_y] = one.normal_line_integral(1, GridQuadrature::Simpson);
+    assert!((n_x - 2_f32).abs() < delta && (n_y - 2_f32).abs() < delta);
+}
@@ -0,0 +1,51 @@
+use crate::functions::GridFunction;
+use crate::geometry::point::Point;
+use crate::numerical_methods::interpolation::{Interpolation, interpolate_grid};
+
+impl GridFunction<f32> {
+    /// The value at the reference coordinates (`ξ`,`η`) interpolated with
+    /// `method`, or `None` if they are outside [0,1]²
+    pub fn value_at_reference(&self, ξ: f32, η: f32, method: Interpolation) -> Option<f32> {
+        if !(0_f32..=1_f32).contains(&ξ) || !(0_f32..=1_f32).contains(&η) {
+            return None
+        }
+        Some(interpolate_grid(
+            &|i, j| self.get_value(i, j), self.n as usize, self.m as usize, ξ / self.h_ξ, η / self.h_η, method,
+        ))
+    }
+
+    /// The value at the physical point `point`, interpolated with `method`
+    /// after inverting the grid mapping interpolated in the same way, or
+    /// `None` if the point is outside the domain
+    pub fn value_at(&self, point: Point<f32>, method: Interpolation) -> Option<f32> {
+        let (ξ, η) = self.domain.reference_coordinates(point, method)?;
+        self.value_at_reference(ξ, η, method)
+    }
+}
+
+#[test]
+fn test_interpolation_on_annulus() {
+    use crate::geometry::domain::Domain;
+    use std::sync::Arc;
+
+    let domain = Arc::new(Domain::quarter_annulus(11, 11));
+    let fnc = |x: f32, y: f32| -> f32 { x.sin() * y };
+    let u = GridFunction::from_fnc(&domain, &fnc);
+    let methods = [Interpolation::Bilinear, Interpolation::Bicubic, Interpolation::Lagrange(4)];
+
+    // Gridpoints are reproduced by every method
+    for method in methods {
+        assert!((u.value_at_reference(0.3, 0.7, method).unwrap() - u.get_value(3, 7)).abs() < 1e-5);
+    }
+    assert!(u.value_at_reference(1.01, 0.5, Interpolation::Bilinear).is_none());
+
+    // Between the gridpoints, and next to the outer arc outside the cells along it
+    for (r, θ) in [(1.37_f32, 0.55_f32), (1.81, 1.23), (1.999, 0.08)] {
+        let (x, y) = (r * θ.cos(), r * θ.sin());
+        let values = methods.map(|method| u.value_at(Point::from(x, y), method));
+        assert!(values.iter().all(|value| value.is_some()));
+        let errors = values.map(|value| (value.unwrap() - fnc(x, y)).abs());
+        assert!(errors[0] < 2e-2 && errors[1] < 1e-3 && errors[2] < 1e-3);
+    }
+    assert!(u.value_at(Point::from(0.5, 0.5), Interpolation::Bicubic).is_none());
+}
@@ -1,73 +1,179 @@
-use crate::geometry::domain::Domain;
+pub mod integration;
+pub mod vector;
+pub mod norms;
+pub mod interpolation;
+pub mod transfer;
+pub mod sbp;
+mod second_derivatives;
+pub mod assembly;
+mod algebra;
+
+use crate::functions::assembly::{pdx_matrix, pdy_matrix, laplace_matrix};
+use crate::geometry::domain::{Domain, BoundarySide};
+use crate::numerical_methods::finite_differences::{DifferenceOrder, line_derivative};
 
 use byteorder::WriteBytesExt;
 use byteorder::LittleEndian;
 
-use nalgebra::DMatrix;
+use nalgebra::{DMatrix, DVector};
 
-use std::ops::Add;
-use std::ops::Mul;
+use std::sync::Arc;
 
+use std::fmt::Display;
 use std::fs::File;
 use std::io::Write;
 
 /// Functions defined on specific domain with values on the 
 /// discrete points on the domain.
-pub struct GridFunction<'a, T> {
-    domain: &'a Domain<T>,
+#[derive(Clone)]
+pub struct GridFunction<T> {
+    domain: Arc<Domain<T>>,
     values: DMatrix<f32>,
     n: u8,
     m: u8,
     h_ξ: f32,
     h_η: f32,
+    order: DifferenceOrder,
 }
 
+/// Error from combining functions that are defined on different domains
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DomainMismatch;
+
+impl Display for DomainMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Can't combine functions defined on different domains")
+    }
+}
+
+impl std::error::Error for DomainMismatch {}
+
+/// Error from values that don't match the number of gridpoints they are for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LengthMismatch {
+    pub expected: usize,
+    pub found: usize,
+}
+
+impl Display for LengthMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Expected {} values but got {}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for LengthMismatch {}
 
 /// Direction for which we take a derivative in 
 /// reference coordinates
 #[allow(non_camel_case_types)]
+#[derive(Copy, Clone)]
 enum DiffDirection {
     ξ,
     η,
 }
 
-impl<'a> GridFunction<'a, f32> {
+impl GridFunction<f32> {
     /// Creates the 0-function on `domain`
-    pub fn new(domain: &'a Domain<f32>) -> GridFunction<f32> {
+    pub fn new(domain: &Arc<Domain<f32>>) -> GridFunction<f32> {
         let n = domain.get_n();
         let m = domain.get_m();
-        let h_ξ = 1_f32 / (n as f32);
-        let h_η = 1_f32 / (n as f32);
+        let h_ξ = domain.get_h_ξ();
+        let h_η = domain.get_h_η();
         // Find if we have a 0 initializer
-        let values = DMatrix::from_fn(n.into(),m.into(),|_,_| 0.0); 
-        GridFunction{domain, values, n, m, h_ξ, h_η}
+        let values = DMatrix::from_fn(m.into(),n.into(),|_,_| 0.0); 
+        GridFunction{domain: domain.clone(), values, n, m, h_ξ, h_η, order: DifferenceOrder::Second}
     }
 
     /// Creates a function from `fnc` on `domain`
-    pub fn from_fnc(domain: &'a Domain<f32>, fnc: &'a dyn Fn(f32,f32) -> f32,) -> GridFunction<'a, f32> {
+    pub fn from_fnc(domain: &Arc<Domain<f32>>, fnc: &dyn Fn(f32,f32) -> f32,) -> GridFunction<f32> {
         let index_fnc = |j: usize, i: usize| -> f32 {
             let xy = domain.get_xy(i,j);
             fnc(xy.get_x(), xy.get_y())
         };
         let n = domain.get_n();
         let m = domain.get_m();
-        let h_ξ = 1_f32 / (n as f32);
-        let h_η = 1_f32 / (n as f32);
+        let h_ξ = domain.get_h_ξ();
+        let h_η = domain.get_h_η();
         let values = DMatrix::from_fn(m.into(), n.into(), index_fnc);
-        GridFunction{domain, values, n, m, h_ξ, h_η}
+        GridFunction{domain: domain.clone(), values, n, m, h_ξ, h_η, order: DifferenceOrder::Second}
     }
 
     /// Creates a function from `domain` and matrix `values`. 
     ///
-    /// Sizes needs to be compatible
-    pub fn from(domain: &'a Domain<f32>, values: DMatrix<f32>,) -> GridFunction<f32> {
+    /// The matrix is `m`×`n`, so that column i holds the values along η
+    /// and the value at index i,j is at position i*m+j like in `to_vector`
+    pub fn from(domain: &Arc<Domain<f32>>, values: DMatrix<f32>,) -> GridFunction<f32> {
         let n = domain.get_n();
         let m = domain.get_m();
-        let h_ξ = 1_f32 / (n as f32);
-        let h_η = 1_f32 / (n as f32);
-        GridFunction{domain, values, n, m, h_ξ, h_η}
+        assert_eq!(values.shape(), (m.into(), n.into()), "Values need to be an m×n matrix");
+        let h_ξ = domain.get_h_ξ();
+        let h_η = domain.get_h_η();
+        GridFunction{domain: domain.clone(), values, n, m, h_ξ, h_η, order: DifferenceOrder::Second}
     }
     
+    /// Creates a function from `domain` and the state vector `values`,
+    /// ordered as `to_vector` orders them
+    pub fn from_vector(domain: &Arc<Domain<f32>>, values: DVector<f32>) -> GridFunction<f32> {
+        let n = domain.get_n();
+        let m = domain.get_m();
+        assert_eq!(values.len(), n as usize * m as usize, "Vector doesn't match the size of the domain");
+        GridFunction::from(domain, DMatrix::from_column_slice(m.into(), n.into(), values.as_slice()))
+    }
+
+    /// The values as a state vector, with the value at index i,j
+    /// at position i*m+j
+    pub fn to_vector(&self) -> DVector<f32> {
+        let size = self.n as usize * self.m as usize;
+        DVector::from_iterator(size, (0..size).map(|k| self.values[k]))
+    }
+
+    /// The function with derivatives taken with the stencils of `order`,
+    /// which the derivatives themselves inherit
+    pub fn with_order(mut self, order: DifferenceOrder) -> GridFunction<f32> {
+        self.order = order;
+        self
+    }
+
+    /// The domain the function is defined on, shared with the other
+    /// functions on it
+    pub fn get_domain(&self) -> &Arc<Domain<f32>> {
+        &self.domain
+    }
+
+    pub fn get_order(&self) -> DifferenceOrder {
+        self.order
+    }
+
+    /// Checks that `other` is defined on the same domain, which needs to
+    /// be the same shared `Domain` and not only an equal one
+    pub fn check_domain(&self, other: &GridFunction<f32>) -> Result<(), DomainMismatch> {
+        self.check_on(&other.domain)
+    }
+
+    /// Checks that the function is defined on the shared `domain`
+    pub(crate) fn check_on(&self, domain: &Arc<Domain<f32>>) -> Result<(), DomainMismatch> {
+        match Arc::ptr_eq(&self.domain, domain) {
+            true => Ok(()),
+            false => Err(DomainMismatch),
+        }
+    }
+
+    /// Applies `f` to each value
+    pub fn map(&self, f: &dyn Fn(f32) -> f32) -> GridFunction<f32> {
+        let values = DMatrix::from_iterator(self.values.nrows(), self.values.ncols(), self.values.iter().map(|u| f(*u)));
+        GridFunction::from(&self.domain, values).with_order(self.order)
+    }
+
+    /// Applies `f` to the values of `self` and `other` at each point
+    pub fn zip_map(&self, other: &GridFunction<f32>, f: &dyn Fn(f32, f32) -> f32) -> Result<GridFunction<f32>, DomainMismatch> {
+        self.check_domain(other)?;
+        let values = DMatrix::from_iterator(
+            self.values.nrows(), self.values.ncols(),
+            self.values.iter().zip(other.values.iter()).map(|(u, v)| f(*u, *v)),
+        );
+        Ok(GridFunction::from(&self.domain, values).with_order(self.order))
+    }
+
     /// Populates `values` with values from `fnc` on the domain
     pub fn generate_function_values(&mut self, fnc: &dyn Fn(f32,f32) -> f32) {
         let index_fnc = |j: usize, i: usize| -> f32 {
@@ -101,202 +207,162 @@ impl<'a> GridFunction<'a, f32> {
         Ok(())
     }
 
-    fn get_value(&self, i: usize, j: usize) -> f32 {
+    pub(crate) fn get_value(&self, i: usize, j: usize) -> f32 {
         self.values[(i as u16 * self.m as u16 + j as u16) as usize]
     }
 
-    pub fn pd_xy(self) -> [GridFunction<'a, f32>; 2] {
-        let mut values_pdx = DMatrix::from_fn(self.n.into(), self.m.into(),|_,_| 0.0); 
-        let mut values_pdy = DMatrix::from_fn(self.n.into(), self.m.into(),|_,_| 0.0); 
-        for i in 0..self.n as usize {
-            for j in 0..self.m as usize {
-                let partial_return_vals = self.partial_derivative(i,j);
-                values_pdx[i * self.m as usize + j] = partial_return_vals[0];
-                values_pdy[i * self.m as usize + j] = partial_return_vals[1];
-            }
-        }
-        [
-            GridFunction::from(&self.domain, values_pdx),
-            GridFunction::from(&self.domain, values_pdy)
-        ]
+    /// The values on boundary side `side`, ordered along its curve as
+    /// `Domain::side_indices`
+    pub fn get_side(&self, side: usize) -> DVector<f32> {
+        let indices = self.domain.side_indices(side);
+        DVector::from_iterator(indices.len(), indices.iter().map(|(i, j)| self.get_value(*i, *j)))
     }
 
-    /// Calculates pdx and pdy from a reference to &self
-    pub fn pd_xy_ref(&'a self) -> [GridFunction<'a, f32>; 2] {
-        let mut values_pdx = DMatrix::from_fn(self.n.into(), self.m.into(),|_,_| 0.0); 
-        let mut values_pdy = DMatrix::from_fn(self.n.into(), self.m.into(),|_,_| 0.0); 
-        for i in 0..self.n as usize {
-            for j in 0..self.m as usize {
-                let partial_return_vals = self.partial_derivative(i,j);
-                values_pdx[i * self.m as usize + j] = partial_return_vals[0];
-                values_pdy[i * self.m as usize + j] = partial_return_vals[1];
-            }
-        }
-        [
-            GridFunction::from(&self.domain, values_pdx),
-            GridFunction::from(&self.domain, values_pdy)
-        ]
+    /// The values on boundary side `side` together with the points, outward
+    /// normals and arc lengths they belong to
+    pub fn get_side_with_geometry(&self, side: usize) -> (DVector<f32>, BoundarySide) {
+        (self.get_side(side), self.domain.side(side))
     }
 
-    pub fn pdx(self) -> GridFunction<'a, f32> {
-        let mut values = DMatrix::from_fn(self.n.into(), self.m.into(),|_,_| 0.0); 
-        for i in 0..self.n as usize {
-            for j in 0..self.m as usize {
-                values[i * self.m as usize + j] = self.partial_derivative(i, j)[0]
-            }
+    /// Overwrites the values on boundary side `side` with `values`, ordered
+    /// as in `get_side`, which need to be as many as the points of the side
+    pub fn set_side(&mut self, side: usize, values: &DVector<f32>) -> Result<(), LengthMismatch> {
+        let indices = self.domain.side_indices(side);
+        if values.len() != indices.len() {
+            return Err(LengthMismatch{expected: indices.len(), found: values.len()})
         }
-        GridFunction::from(&self.domain, values)
-    }
-
-    /// Calculates pdx from a reference to &self
-    pub fn pdx_ref(&self) -> GridFunction<'a, f32> {
-        let mut values = DMatrix::from_fn(self.n.into(), self.m.into(),|_,_| 0.0); 
-        for i in 0..self.n as usize {
-            for j in 0..self.m as usize {
-                values[i * self.m as usize + j] = self.partial_derivative(i, j)[0]
-            }
+        for ((i, j), value) in indices.iter().zip(values.iter()) {
+            self.values[i * self.m as usize + j] = *value;
         }
-        GridFunction::from(&self.domain, values)
+        Ok(())
     }
 
-    pub fn pdy(self) -> GridFunction<'a, f32> {
-        let mut values = DMatrix::from_fn(self.n.into(), self.m.into(),|_,_| 0.0); 
-        for i in 0..self.n as usize {
-            for j in 0..self.m as usize {
-                values[i * self.m as usize + j] = self.partial_derivative(i, j)[1]
-            }
-        }
-        GridFunction::from(&self.domain, values)
+    /// Calculates the partial derivatives in x- and y-direction
+    pub fn pd_xy(&self) -> [GridFunction<f32>; 2] {
+        [self.pdx(), self.pdy()]
     }
 
-    /// Calculates pdy from a reference to &self
-    pub fn pdy_ref(&self) -> GridFunction<'a, f32> {
-        let mut values = DMatrix::from_fn(self.n.into(), self.m.into(),|_,_| 0.0); 
-        for i in 0..self.n as usize {
-            for j in 0..self.m as usize {
-                values[i * self.m as usize + j] = self.partial_derivative(i, j)[1]
-            }
-        }
-        GridFunction::from(&self.domain, values)
+    /// Applies the assembled `pdx_matrix`, so both give the same values
+    pub fn pdx(&self) -> GridFunction<f32> {
+        self.apply(&pdx_matrix(&self.domain, self.order))
     }
 
-    pub fn laplace(&self) -> GridFunction<f32> {
-        let [dx,dy] = self.pd_xy_ref();
-        dx.pdx() + dy.pdy()
+    /// Applies the assembled `pdy_matrix`, so both give the same values
+    pub fn pdy(&self) -> GridFunction<f32> {
+        self.apply(&pdy_matrix(&self.domain, self.order))
     }
 
-    /// Calculates the partial derivatives in both x- and y-directionsa at index i,j,
-    /// returns a list of the values
-    #[allow(non_snake_case)]
-    fn partial_derivative(&self, i: usize, j: usize) -> [f32; 2] {
-        let x = |i: usize, j: usize| -> f32 {self.domain.get_xy(i,j).get_x()};
-        let y = |i: usize, j: usize| -> f32 {self.domain.get_xy(i,j).get_y()};
-        let f = |i: usize, j: usize| -> f32 {self.get_value(i,j)};
-
-        let x_ξ = self.partial_derivative_of_fnc(&x, DiffDirection::ξ, i, j);
-        let x_η = self.partial_derivative_of_fnc(&x, DiffDirection::η, i, j);
-        let y_ξ = self.partial_derivative_of_fnc(&y, DiffDirection::ξ, i, j);
-        let y_η = self.partial_derivative_of_fnc(&y, DiffDirection::η, i, j);
-
-        let u_ξ = self.partial_derivative_of_fnc(&f, DiffDirection::ξ, i, j);
-        let u_η = self.partial_derivative_of_fnc(&f, DiffDirection::η, i, j);
-
-        let det_J = x_ξ * y_η - x_η * y_ξ;
-
-        let u_x = (u_ξ * y_η - u_η * y_ξ) / det_J;
-        let u_y = (u_η * x_ξ - u_ξ * y_η) / det_J;
-
-        return [u_x, u_y]
+    /// Applies the assembled `laplace_matrix`, so both give the same values
+    pub fn laplace(&self) -> GridFunction<f32> {
+        self.apply(&laplace_matrix(&self.domain, self.order))
     }
 
     fn partial_derivative_of_fnc(
         &self, f: &dyn Fn(usize, usize) -> f32, dir: DiffDirection, i: usize, j: usize) -> f32 
     {
-        let last_index: usize;
-        let current_index: usize;
-        let is: [usize; 4];
-        let js: [usize; 4];
-        let h: f32;
         match dir {
-            DiffDirection::ξ => {
-                current_index = i;
-                last_index = (self.n-1) as usize;
-                h = self.h_ξ;
-                is = set_indices(i, true);
-                js = set_indices(j, false);
-            },
-            DiffDirection::η => {
-                current_index = j;
-                last_index = (self.m-1) as usize;
-                h = self.h_η;
-                is = set_indices(i, false);
-                js = set_indices(j, true);
-            }
-        }
-        if current_index == 0 {
-            one_sided_diff(-1, f(i,j), f(is[0],js[0]), f(is[2],js[2]), h)
-        } else if current_index == last_index {
-            one_sided_diff(1, f(i,j), f(is[1],js[1]), f(is[3],js[3]), h)
-        } else {
-            central_diff(f(is[0],js[0]), f(is[1],js[1]), h)
+            DiffDirection::ξ => line_derivative(&|k: usize| f(k, j), i, (self.n-1) as usize, self.h_ξ, self.order),
+            DiffDirection::η => line_derivative(&|k: usize| f(i, k), j, (self.m-1) as usize, self.h_η, self.order),
         }
     }
 }
 
-impl<'a> Add for GridFunction<'a, f32> {
-    type Output = GridFunction<'a, f32>;
-    fn add(self, other: GridFunction<'a, f32>) -> Self::Output {
-        let values = match std::ptr::eq(self.domain, other.domain) {
-            true => self.values + other.values,
-            false => panic!("Can't add functions defined on different domains."),
-        };
-        GridFunction::from(self.domain, values)
+#[test]
+fn test_partial_derivatives_on_annulus() {
+    let domain = Arc::new(Domain::quarter_annulus(31, 21));
+    let u = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x*x*y);
+    let [u_x, u_y] = u.pd_xy();
+    for i in 0..31 {
+        for j in 0..21 {
+            let xy = domain.get_xy(i, j);
+            let (x, y) = (xy.get_x(), xy.get_y());
+            assert!((u_x.get_value(i, j) - 2_f32*x*y).abs() < 1e-2 * (1_f32 + 2_f32*x*y));
+            assert!((u_y.get_value(i, j) - x*x).abs() < 1e-2 * (1_f32 + x*x));
+        }
     }
 }
 
-impl<'a> Mul for GridFunction<'a, f32> {
-    type Output = GridFunction<'a, f32>;
-    fn mul(self, other: GridFunction<'a, f32>) -> Self::Output {
-        let values = match std::ptr::eq(self.domain, other.domain) {
-            true => self.values.component_mul(&other.values),
-            false => panic!("Can't multiply functions defined on different domains."),
-        };
-        GridFunction::from(self.domain, values)
-    }
+#[test]
+fn test_higher_order_derivatives() {
+    let domain = Arc::new(Domain::quarter_annulus(21, 21));
+    let max_error = |order: DifferenceOrder| -> [f32; 2] {
+        let u = GridFunction::from_fnc(&domain, &|x: f32, y: f32| (2_f32*x).sin() * y.cos()).with_order(order);
+        let [u_x, u_y] = u.pd_xy();
+        let laplace = u.laplace();
+        assert_eq!(laplace.get_order(), order);
+        let (mut error, mut laplace_error) = (0_f32, 0_f32);
+        for i in 0..21 {
+            for j in 0..21 {
+                let xy = domain.get_xy(i, j);
+                let (x, y) = (xy.get_x(), xy.get_y());
+                error = error
+                    .max((u_x.get_value(i, j) - 2_f32*(2_f32*x).cos()*y.cos()).abs())
+                    .max((u_y.get_value(i, j) + (2_f32*x).sin()*y.sin()).abs());
+                laplace_error = laplace_error.max((laplace.get_value(i, j) + 5_f32*(2_f32*x).sin()*y.cos()).abs());
+            }
+        }
+        [error, laplace_error]
+    };
+    let [second, fourth, sixth] = DifferenceOrder::ALL.map(max_error);
+    assert!(fourth[0] < 0.2 * second[0] && sixth[0] < fourth[0]);
+    assert!(fourth[1] < 0.2 * second[1] && sixth[1] < fourth[1]);
 }
 
-impl<'a, 'b> Add<&'b GridFunction<'b, f32>> for &'a GridFunction<'a, f32> {
-    type Output = GridFunction<'a, f32>;
-    fn add(self, other: &'b GridFunction<f32>) -> Self::Output {
-        let values = match std::ptr::eq(self.domain, other.domain) {
-            true => &self.values + &other.values,
-            false => panic!("Can't add functions defined on different domains."),
-        };
-        GridFunction::from(self.domain, values)
+#[test]
+fn test_shared_domain_across_threads() {
+    let domain = Arc::new(Domain::unit_square(5, 5));
+    let u = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x + 2_f32*y);
+    let u_x = std::thread::spawn(move || u.pdx()).join().unwrap();
+    assert!(Arc::ptr_eq(u_x.get_domain(), &domain));
+    let sum = (u_x + GridFunction::from_fnc(&domain, &|_: f32, _: f32| 1_f32)).unwrap();
+    for i in 0..5 {
+        for j in 0..5 {
+            assert!((sum.get_value(i, j) - 2_f32).abs() < 1e-5);
+        }
     }
-}
 
-fn one_sided_diff(side: i8, s0: f32, s1: f32, s2: f32, h: f32) -> f32 {
-    return (side as f32)*(3_f32*s0 - 4_f32*s1 + s2) / (2_f32*h)
+    // Every constructor lays the values out the same way when n ≠ m
+    let domain = Arc::new(Domain::unit_square(4, 6));
+    let u = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x + 2_f32*y);
+    let v = GridFunction::from_vector(&domain, u.to_vector());
+    let w = (&u.pdx() - &GridFunction::new(&domain)).unwrap();
+    assert_eq!(u.values, v.values);
+    assert_eq!(u.values.shape(), w.values.shape());
+    assert!((v.get_value(3, 5) - 3_f32).abs() < 1e-6 && (w.get_value(2, 1) - 1_f32).abs() < 1e-4);
 }
 
-fn central_diff(sp1: f32, sm1: f32, h: f32) -> f32 {
-    return (sp1 - sm1) / (2_f32*h)
-}
-
-fn set_indices(i0: usize, change: bool) -> [usize; 4] {
-    match change {
-        true => {
-            match i0 {
-                // If i0 is 0 or 1 we don't use the negative values
-                // and so we dont do the subtractions since we have usize values
-                0 => [i0+1, i0, i0+2, i0],
-                1 => [i0+1, i0-1, i0+2, i0],
-                _ => [i0+1, i0-1, i0+2, i0-2],
-            }
-        },
-        false => {
-            return [i0, i0, i0, i0]
+#[test]
+fn test_boundary_sides_of_annulus() {
+    use std::f32::consts::PI;
+
+    let domain = Arc::new(Domain::quarter_annulus(6, 9));
+    let delta = 1e-3;
+    let mut u = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x + 10_f32 * y);
+
+    // Every side starts where its curve starts, and the normals point away from the annulus
+    let starts = [(1_f32, 0_f32), (2_f32, 0_f32), (0_f32, 1_f32), (1_f32, 0_f32)];
+    let lengths = [1_f32, PI, 1_f32, PI / 2_f32];
+    for side in 0..4 {
+        let (values, geometry) = u.get_side_with_geometry(side);
+        let (x, y) = starts[side];
+        assert!((values[0] - (x + 10_f32 * y)).abs() < delta);
+        assert!((geometry.arc_lengths.last().unwrap() - lengths[side]).abs() < delta);
+        for ((i, j), normal) in geometry.indices.iter().zip(geometry.normals.iter()) {
+            let xy = domain.get_xy(*i, *j);
+            let r = (xy.get_x().powi(2) + xy.get_y().powi(2)).sqrt();
+            let expected = match side {
+                0 => (0_f32, -1_f32),
+                1 => (xy.get_x() / r, xy.get_y() / r),
+                2 => (-1_f32, 0_f32),
+                _ => (-xy.get_x() / r, -xy.get_y() / r),
+            };
+            assert!((normal.get_x() - expected.0).abs() < delta && (normal.get_y() - expected.1).abs() < delta);
         }
     }
+
+    let outer = DVector::from_fn(9, |k, _| k as f32);
+    u.set_side(1, &outer).unwrap();
+    assert_eq!(u.get_side(1), outer);
+    assert_eq!(u.set_side(0, &outer), Err(LengthMismatch{expected: 6, found: 9}));
+    assert_eq!(u.get_value(5, 3), 3_f32);
 }
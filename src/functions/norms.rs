@@ -0,0 +1,72 @@
+use crate::functions::GridFunction;
+use crate::functions::integration::GridQuadrature;
+
+/// Discrete norms of a function, where the integrals are weighted by the
+/// area around each gridpoint from the trapezoidal rule and the Jacobian
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Norms {
+    /// ∫∫ |u| dx dy
+    pub l1: f32,
+    /// (∫∫ u² dx dy)^½
+    pub l2: f32,
+    /// max |u| over the gridpoints
+    pub max: f32,
+    /// (∫∫ |∇u|² dx dy)^½, with the gradient from the differences of the function
+    pub h1_seminorm: f32,
+}
+
+impl GridFunction<f32> {
+    pub fn l1_norm(&self) -> f32 {
+        self.integrate_values(GridQuadrature::Trapezoid, &|u: f32| u.abs())
+    }
+
+    pub fn l2_norm(&self) -> f32 {
+        self.integrate_values(GridQuadrature::Trapezoid, &|u: f32| u * u).sqrt()
+    }
+
+    pub fn max_norm(&self) -> f32 {
+        self.values.iter().fold(0_f32, |max, u| max.max(u.abs()))
+    }
+
+    pub fn h1_seminorm(&self) -> f32 {
+        let [u_x, u_y] = self.pd_xy();
+        u_x.zip_map(&u_y, &|u_x, u_y| u_x * u_x + u_y * u_y)
+            .expect("Derivatives are defined on the same domain")
+            .integrate(GridQuadrature::Trapezoid)
+            .sqrt()
+    }
+
+    /// All of the norms of the function
+    pub fn norms(&self) -> Norms {
+        Norms{l1: self.l1_norm(), l2: self.l2_norm(), max: self.max_norm(), h1_seminorm: self.h1_seminorm()}
+    }
+
+    /// The norms of u - `fnc`, where `fnc` is evaluated on the same domain
+    /// and differentiated with the same stencils
+    pub fn error_against(&self, fnc: &dyn Fn(f32, f32) -> f32) -> Norms {
+        let exact = GridFunction::from_fnc(&self.domain, fnc);
+        (self - exact).expect("Both are defined on the same domain").norms()
+    }
+}
+
+#[test]
+fn test_norms_on_unit_square() {
+    use crate::geometry::domain::Domain;
+    use std::sync::Arc;
+
+    let domain = Arc::new(Domain::unit_square(41, 21));
+    let delta = 1e-4;
+
+    // u = 2x - 1 changes sign, ∫|u| = 1/2 and ∫u² = 1/3 plus the trapezoidal error 2h²/3
+    let u = GridFunction::from_fnc(&domain, &|x: f32, _: f32| 2_f32 * x - 1_f32);
+    let norms = u.norms();
+    assert!((norms.l1 - 0.5).abs() < delta);
+    assert!((norms.l2 - (1_f32 / 3_f32 + 1_f32 / 2400_f32).sqrt()).abs() < delta);
+    assert!((norms.max - 1_f32).abs() < delta);
+    assert!((norms.h1_seminorm - 2_f32).abs() < delta);
+
+    let v = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x * y);
+    let error = v.error_against(&|x: f32, y: f32| x * y + 0.1);
+    assert!((error.l1 - 0.1).abs() < delta && (error.l2 - 0.1).abs() < delta && (error.max - 0.1).abs() < delta);
+    assert!(error.h1_seminorm < delta);
+}
@@ -0,0 +1,276 @@
+//! Summation-by-parts operators on curvilinear grids, a family next to the
+//! finite differences of `GridFunction::pd_xy` for time-dependent problems
+//! that need an energy estimate. Boundary conditions are imposed weakly
+//! with simultaneous approximation terms (SAT).
+use crate::functions::{GridFunction, DomainMismatch, LengthMismatch};
+use crate::geometry::domain::Domain;
+use crate::numerical_methods::finite_differences::DifferenceOrder;
+use crate::numerical_methods::sbp::{SbpOperator, TooFewPoints};
+
+use nalgebra::DVector;
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+/// Error from the boundary terms of `SbpOperators`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SatError {
+    /// The function is defined on another domain than the operators
+    DomainMismatch,
+    /// The boundary data or penalties don't match the points of the side
+    Length(LengthMismatch),
+    /// The side isn't one of the four sides 0..=3 of the domain
+    Side(usize),
+}
+
+impl Display for SatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SatError::DomainMismatch => DomainMismatch.fmt(f),
+            SatError::Length(mismatch) => mismatch.fmt(f),
+            SatError::Side(side) => write!(f, "Side should be between 0..3, found {}", side),
+        }
+    }
+}
+
+impl std::error::Error for SatError {}
+
+impl From<DomainMismatch> for SatError {
+    fn from(_: DomainMismatch) -> SatError {
+        SatError::DomainMismatch
+    }
+}
+
+/// Summation-by-parts operators in the reference directions ξ and η of a
+/// domain, combined with the metric terms the domain computed with
+/// differences of the same order.
+///
+/// The norm is H = H_ξ ⊗ H_η scaled by |J|.
+#[derive(Clone)]
+pub struct SbpOperators {
+    domain: Arc<Domain<f32>>,
+    ξ: SbpOperator,
+    η: SbpOperator,
+}
+
+impl SbpOperators {
+    /// The operators of `order` on `domain`, if it has enough points in
+    /// both directions
+    pub fn from(domain: &Arc<Domain<f32>>, order: DifferenceOrder) -> Result<SbpOperators, TooFewPoints> {
+        Ok(SbpOperators{
+            domain: Arc::clone(domain),
+            ξ: SbpOperator::from(order, domain.get_n() as usize, domain.get_h_ξ())?,
+            η: SbpOperator::from(order, domain.get_m() as usize, domain.get_h_η())?,
+        })
+    }
+
+    pub fn get_order(&self) -> DifferenceOrder {
+        self.ξ.get_order()
+    }
+
+    pub fn get_ξ(&self) -> &SbpOperator {
+        &self.ξ
+    }
+
+    pub fn get_η(&self) -> &SbpOperator {
+        &self.η
+    }
+
+    /// The diagonal of the norm as a function on the domain, which are
+    /// also quadrature weights over the physical domain
+    pub fn norm(&self) -> GridFunction<f32> {
+        let m = self.domain.get_m() as usize;
+        let values = DVector::from_fn(self.points(), |k, _| {
+            let (i, j) = (k / m, k % m);
+            self.ξ.get_norm()[i] * self.η.get_norm()[j] * self.metric(i, j).jacobian.abs()
+        });
+        GridFunction::from_vector(&self.domain, values).with_order(self.get_order())
+    }
+
+    /// The inner product uᵀHv in the norm of the operators
+    pub fn inner_product(&self, u: &GridFunction<f32>, v: &GridFunction<f32>) -> Result<f32, DomainMismatch> {
+        u.check_on(&self.domain)?;
+        u.check_domain(v)?;
+        Ok(self.norm().values.iter().zip(u.values.iter().zip(v.values.iter())).map(|(w, (a, b))| w * a * b).sum())
+    }
+
+    /// The partial derivatives in x- and y-direction
+    pub fn pd_xy(&self, u: &GridFunction<f32>) -> Result<[GridFunction<f32>; 2], DomainMismatch> {
+        u.check_on(&self.domain)?;
+        let u_ξ = self.derivative_ξ(&|i, j| u.get_value(i, j));
+        let u_η = self.derivative_η(&|i, j| u.get_value(i, j));
+        let m = self.domain.get_m() as usize;
+        let mut values_x = DVector::zeros(self.points());
+        let mut values_y = DVector::zeros(self.points());
+        for k in 0..self.points() {
+            let [ξ_x, ξ_y, η_x, η_y] = self.metric(k / m, k % m).inverse();
+            values_x[k] = u_ξ[k] * ξ_x + u_η[k] * η_x;
+            values_y[k] = u_ξ[k] * ξ_y + u_η[k] * η_y;
+        }
+        Ok([
+            GridFunction::from_vector(&self.domain, values_x).with_order(self.get_order()),
+            GridFunction::from_vector(&self.domain, values_y).with_order(self.get_order()),
+        ])
+    }
+
+    /// The simultaneous approximation term -H⁻¹ e σ (u - g) that weakly
+    /// imposes u = g on side `side`, as a contribution to u_t.
+    ///
+    /// `data` holds g and `penalty` the strengths σ ≥ 0, both ordered as
+    /// `Domain::side_indices` with one value for each point of the side.
+    pub fn sat(
+        &self, u: &GridFunction<f32>, side: usize, data: &DVector<f32>, penalty: &DVector<f32>,
+    ) -> Result<GridFunction<f32>, SatError> {
+        u.check_on(&self.domain)?;
+        let (n, m) = (self.domain.get_n() as usize, self.domain.get_m() as usize);
+        // Weight of the norm in the direction normal to the side
+        let weight = match side {
+            0 => self.η.get_norm()[0],
+            1 => self.ξ.get_norm()[n - 1],
+            2 => self.η.get_norm()[m - 1],
+            3 => self.ξ.get_norm()[0],
+            _ => return Err(SatError::Side(side)),
+        };
+        let indices = self.domain.side_indices(side);
+        for found in [data.len(), penalty.len()] {
+            if found != indices.len() {
+                return Err(SatError::Length(LengthMismatch{expected: indices.len(), found}))
+            }
+        }
+        let mut values = DVector::zeros(self.points());
+        for (k, (i, j)) in indices.iter().enumerate() {
+            values[i * m + j] = -penalty[k] * (u.get_value(*i, *j) - data[k])
+                / (weight * self.metric(*i, *j).jacobian.abs());
+        }
+        Ok(GridFunction::from_vector(&self.domain, values).with_order(self.get_order()))
+    }
+
+    /// The time derivative u_t of the advection equation u_t + a·∇u = 0 with
+    /// constant `velocity` a, and the values `inflow` at the inflow boundary.
+    ///
+    /// The transformed equation |J| u_t + ã u_ξ + b̃ u_η = 0 is discretized in
+    /// the skew-symmetric split form ½(D ã + ã D), whose energy only changes
+    /// through the boundaries, and upwind SATs make that change non-positive
+    /// for zero inflow.
+    pub fn advection(
+        &self, u: &GridFunction<f32>, velocity: [f32; 2], inflow: &dyn Fn(f32, f32) -> f32,
+    ) -> Result<GridFunction<f32>, SatError> {
+        u.check_on(&self.domain)?;
+        let m = self.domain.get_m() as usize;
+        let [a, b] = velocity;
+        let sign = self.metric(0, 0).jacobian.signum();
+        // Contravariant velocities scaled by |J|
+        let flux = |i: usize, j: usize| -> [f32; 2] {
+            let metric = self.metric(i, j);
+            [sign * (a * metric.y_η - b * metric.x_η), sign * (b * metric.x_ξ - a * metric.y_ξ)]
+        };
+        let u_ξ = self.derivative_ξ(&|i, j| u.get_value(i, j));
+        let u_η = self.derivative_η(&|i, j| u.get_value(i, j));
+        let flux_ξ = self.derivative_ξ(&|i, j| flux(i, j)[0] * u.get_value(i, j));
+        let flux_η = self.derivative_η(&|i, j| flux(i, j)[1] * u.get_value(i, j));
+        let values = DVector::from_fn(self.points(), |k, _| {
+            let (i, j) = (k / m, k % m);
+            let [ã, b̃] = flux(i, j);
+            -0.5 * (flux_ξ[k] + ã * u_ξ[k] + flux_η[k] + b̃ * u_η[k]) / self.metric(i, j).jacobian.abs()
+        });
+        let mut u_t = GridFunction::from_vector(&self.domain, values).with_order(self.get_order());
+
+        for side in 0..4 {
+            let indices = self.domain.side_indices(side);
+            // Velocity into the domain through the side
+            let inward = |i: usize, j: usize| -> f32 {
+                match side {
+                    0 => flux(i, j)[1],
+                    1 => -flux(i, j)[0],
+                    2 => -flux(i, j)[1],
+                    _ => flux(i, j)[0],
+                }
+            };
+            let penalty = DVector::from_iterator(indices.len(), indices.iter().map(|(i, j)| inward(*i, *j).max(0_f32)));
+            let data = DVector::from_iterator(indices.len(), indices.iter().map(|(i, j)| {
+                let xy = self.domain.get_xy(*i, *j);
+                inflow(xy.get_x(), xy.get_y())
+            }));
+            u_t.try_add_assign(&self.sat(u, side, &data, &penalty)?)?;
+        }
+        Ok(u_t)
+    }
+
+    fn points(&self) -> usize {
+        self.domain.get_n() as usize * self.domain.get_m() as usize
+    }
+
+    fn metric(&self, i: usize, j: usize) -> crate::geometry::metric::Metric {
+        self.domain.get_metric_of_order(i, j, self.get_order())
+    }
+
+    /// D_ξ applied to the values `f(i,j)`, indexed as i*m+j
+    fn derivative_ξ(&self, f: &dyn Fn(usize, usize) -> f32) -> DVector<f32> {
+        let m = self.domain.get_m() as usize;
+        DVector::from_fn(self.points(), |k, _| self.ξ.apply(&|l| f(l, k % m), k / m))
+    }
+
+    /// D_η applied to the values `f(i,j)`, indexed as i*m+j
+    fn derivative_η(&self, f: &dyn Fn(usize, usize) -> f32) -> DVector<f32> {
+        let m = self.domain.get_m() as usize;
+        DVector::from_fn(self.points(), |k, _| self.η.apply(&|l| f(k / m, l), k % m))
+    }
+}
+
+#[test]
+fn test_sbp_energy_estimate_on_annulus() {
+    use crate::numerical_methods::ode::rk4;
+
+    let domain = Arc::new(Domain::quarter_annulus(17, 15));
+    let velocity = [1_f32, 0.5_f32];
+    // Rough data, where only the boundary terms can keep the energy bounded
+    let rough = GridFunction::from_vector(&domain, DVector::from_fn(17 * 15, |k, _| ((k * k) as f32 * 0.37).sin()));
+
+    let mut gradient_errors = Vec::new();
+    for order in DifferenceOrder::ALL {
+        let sbp = SbpOperators::from(&domain, order).unwrap();
+        // Linear functions aren't linear in the reference coordinates, so
+        // the error of the gradient decreases with the order
+        let [u_x, u_y] = sbp.pd_xy(&GridFunction::from_fnc(&domain, &|x, y| 2_f32 * x - y)).unwrap();
+        gradient_errors.push(
+            u_x.values.iter().chain(u_y.values.iter()).zip([2_f32, -1_f32].iter().flat_map(|d| std::iter::repeat_n(*d, 17 * 15)))
+                .map(|(v, d)| (v - d).abs()).fold(0_f32, f32::max)
+        );
+
+        // dE/dt = 2uᵀH u_t is non-positive for zero inflow
+        let u_t = sbp.advection(&rough, velocity, &|_, _| 0_f32).unwrap();
+        let energy = sbp.inner_product(&rough, &rough).unwrap();
+        assert!(2_f32 * sbp.inner_product(&rough, &u_t).unwrap() < 1e-5 * energy);
+
+        // and the energy decays in time
+        let f = |_t: f32, y: &DVector<f32>| -> DVector<f32> {
+            let u = GridFunction::from_vector(&domain, y.clone());
+            sbp.advection(&u, velocity, &|_, _| 0_f32).unwrap().to_vector()
+        };
+        let solution = rk4(&f, rough.to_vector(), 0_f32, 0.1_f32, 40);
+        let u = GridFunction::from_vector(&domain, solution.y);
+        assert!(sbp.inner_product(&u, &u).unwrap() < energy);
+    }
+    assert!(gradient_errors[0] < 0.15 && gradient_errors[1] < 0.2 * gradient_errors[0] && gradient_errors[2] < 0.2 * gradient_errors[1]);
+
+    // A travelling wave with its inflow data
+    let exact = |x: f32, y: f32, t: f32| -> f32 { (x - velocity[0] * t + 2_f32 * (y - velocity[1] * t)).sin() };
+    let error = |order: DifferenceOrder| -> f32 {
+        let sbp = SbpOperators::from(&domain, order).unwrap();
+        let f = |t: f32, y: &DVector<f32>| -> DVector<f32> {
+            let u = GridFunction::from_vector(&domain, y.clone());
+            sbp.advection(&u, velocity, &|x, y| exact(x, y, t)).unwrap().to_vector()
+        };
+        let initial = GridFunction::from_fnc(&domain, &|x, y| exact(x, y, 0_f32));
+        let solution = rk4(&f, initial.to_vector(), 0_f32, 0.5_f32, 100);
+        GridFunction::from_vector(&domain, solution.y).error_against(&|x, y| exact(x, y, 0.5_f32)).max
+    };
+    assert!(error(DifferenceOrder::Second) < 0.1);
+    assert!(error(DifferenceOrder::Fourth) < 0.5 * error(DifferenceOrder::Second));
+
+    let sbp = SbpOperators::from(&domain, DifferenceOrder::Second).unwrap();
+    let (data, penalty) = (DVector::zeros(17), DVector::zeros(15));
+    assert_eq!(sbp.sat(&rough, 0, &data, &penalty).err(), Some(SatError::Length(LengthMismatch{expected: 17, found: 15})));
+    assert_eq!(sbp.sat(&rough, 4, &data, &data).err(), Some(SatError::Side(4)));
+    assert!(SbpOperators::from(&Arc::new(Domain::unit_square(17, 9)), DifferenceOrder::Fourth).is_err());
+}
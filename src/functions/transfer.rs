@@ -0,0 +1,168 @@
+//! Transfer of grid functions between domains built from the same boundary
+//! curves with different resolutions, where the gridpoints with the same
+//! reference coordinates coincide.
+use crate::functions::GridFunction;
+use crate::geometry::domain::Domain;
+use crate::numerical_methods::interpolation::{Interpolation, interpolate_grid};
+
+use nalgebra::DVector;
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+/// Error from transferring a function to a domain it can't be transferred to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TransferError {
+    /// The domains aren't built from the same boundary curves
+    DomainMismatch,
+    /// The grid lines of the coarse domain aren't every r:th grid line of
+    /// the fine domain, with the number of points of the two along a line
+    Ratio{fine: usize, coarse: usize},
+}
+
+impl Display for TransferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransferError::DomainMismatch => write!(f, "Domains don't share their boundary"),
+            TransferError::Ratio{fine, coarse} => write!(f, "{} points can't be restricted to {} points", fine, coarse),
+        }
+    }
+}
+
+impl std::error::Error for TransferError {}
+
+/// Restriction from a finer to a coarser grid
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Restriction {
+    /// The value at the coinciding fine gridpoint
+    Injection,
+    /// Weighted average of the fine gridpoints around the coarse gridpoint
+    /// with the weights of linear interpolation, which for a refinement
+    /// ratio of 2 is the stencil [1/4 1/2 1/4] in each direction.
+    /// Boundary values are injected in the direction normal to the boundary.
+    FullWeighting,
+}
+
+impl GridFunction<f32> {
+    /// Restricts the function to `coarse`, whose grid lines need to be
+    /// every r:th grid line of this domain for integer ratios r
+    pub fn restrict(&self, coarse: &Arc<Domain<f32>>, method: Restriction) -> Result<GridFunction<f32>, TransferError> {
+        check_same_boundary(&self.domain, coarse)?;
+        let (n, m) = (coarse.get_n() as usize, coarse.get_m() as usize);
+        let r_ξ = ratio(self.n as usize, n)?;
+        let r_η = ratio(self.m as usize, m)?;
+        let weights_ξ = |i: usize| line_weights(method, i, n - 1, r_ξ);
+        let weights_η = |j: usize| line_weights(method, j, m - 1, r_η);
+        let values = DVector::from_iterator(n * m, (0..n*m).map(|k| {
+            let (i, j) = (k / m, k % m);
+            let mut value = 0_f32;
+            for (i_fine, w_i) in weights_ξ(i) {
+                for (j_fine, w_j) in weights_η(j) {
+                    value += w_i * w_j * self.get_value(i_fine, j_fine);
+                }
+            }
+            value
+        }));
+        Ok(GridFunction::from_vector(coarse, values).with_order(self.order))
+    }
+
+    /// Prolongs the function to `fine` by interpolating with `method` at
+    /// its gridpoints, for any resolution of `fine`
+    pub fn prolongate(&self, fine: &Arc<Domain<f32>>, method: Interpolation) -> Result<GridFunction<f32>, TransferError> {
+        check_same_boundary(&self.domain, fine)?;
+        let (n, m) = (fine.get_n() as usize, fine.get_m() as usize);
+        let (h_ξ, h_η) = (fine.get_h_ξ(), fine.get_h_η());
+        let values = DVector::from_iterator(n * m, (0..n*m).map(|k| {
+            let (i, j) = (k / m, k % m);
+            interpolate_grid(
+                &|i, j| self.get_value(i, j), self.n as usize, self.m as usize,
+                (i as f32) * h_ξ / self.h_ξ, (j as f32) * h_η / self.h_η, method,
+            )
+        }));
+        Ok(GridFunction::from_vector(fine, values).with_order(self.order))
+    }
+}
+
+/// Refinement ratio between grid lines of `fine` and `coarse` points
+fn ratio(fine: usize, coarse: usize) -> Result<usize, TransferError> {
+    match coarse > 1 && (fine - 1).is_multiple_of(coarse - 1) {
+        true => Ok((fine - 1) / (coarse - 1)),
+        false => Err(TransferError::Ratio{fine, coarse}),
+    }
+}
+
+/// Fine indices and weights for the coarse index `k` of a line with
+/// indices `0..=last` and refinement ratio `r`
+fn line_weights(method: Restriction, k: usize, last: usize, r: usize) -> Vec<(usize, f32)> {
+    if method == Restriction::Injection || k == 0 || k == last {
+        return vec![(k * r, 1_f32)]
+    }
+    let scale = (r * r) as f32;
+    (1 - r as isize..r as isize)
+        .map(|d| ((k * r) as isize + d) as usize)
+        .zip((1 - r as isize..r as isize).map(|d| (r as isize - d.abs()) as f32 / scale))
+        .collect()
+}
+
+/// Checks that the boundary gridpoints of each domain lie on the boundary of
+/// the other, as they do for domains built from the same boundary curves
+fn check_same_boundary(domain: &Domain<f32>, other: &Domain<f32>) -> Result<(), TransferError> {
+    let on_boundary_of = |d: &Domain<f32>, samples: &Domain<f32>| -> bool {
+        (0..4).all(|side| samples.side_indices(side).iter().all(|(i, j)| {
+            let (ξ, η) = ((*i as f32) * samples.get_h_ξ(), (*j as f32) * samples.get_h_η());
+            d.map_transfinite(ξ, η).equal(&samples.get_xy(*i, *j))
+        }))
+    };
+    match on_boundary_of(domain, other) && on_boundary_of(other, domain) {
+        true => Ok(()),
+        false => Err(TransferError::DomainMismatch),
+    }
+}
+
+#[test]
+fn test_transfer_between_resolutions() {
+    use crate::geometry::point::Point;
+    use crate::curve_impl::straight_line::StraightLine;
+    use crate::curve_impl::circle_arc::CircleArc;
+    use std::f32::consts::PI;
+
+    let (coarse, fine) = (Arc::new(Domain::quarter_annulus(9, 7)), Arc::new(Domain::quarter_annulus(17, 13)));
+    let fnc = |x: f32, y: f32| -> f32 { x.sin() * y };
+    let u_fine = GridFunction::from_fnc(&fine, &fnc);
+    let u_coarse = GridFunction::from_fnc(&coarse, &fnc);
+
+    // Injection picks the coinciding values, full weighting is second order
+    // accurate and removes the highest frequency
+    let injected = u_fine.restrict(&coarse, Restriction::Injection).unwrap();
+    let weighted = u_fine.restrict(&coarse, Restriction::FullWeighting).unwrap();
+    let checkerboard = DVector::from_fn(17 * 13, |k, _| (-1_f32).powi((k / 13 + k % 13) as i32));
+    let checkerboard = GridFunction::from_vector(&fine, checkerboard).restrict(&coarse, Restriction::FullWeighting).unwrap();
+    for i in 0..9 {
+        for j in 0..7 {
+            assert!((injected.get_value(i, j) - u_coarse.get_value(i, j)).abs() < 1e-5);
+            assert!((weighted.get_value(i, j) - u_coarse.get_value(i, j)).abs() < 5e-2);
+            if i > 0 && i < 8 && j > 0 && j < 6 {
+                assert!(checkerboard.get_value(i, j).abs() < 1e-6);
+            }
+        }
+    }
+
+    // Cubic prolongation is more accurate than bilinear
+    let error = |method: Interpolation| -> f32 {
+        u_coarse.prolongate(&fine, method).unwrap().error_against(&fnc).max
+    };
+    assert!(error(Interpolation::Bicubic) < 0.1 * error(Interpolation::Bilinear));
+    assert!(error(Interpolation::Lagrange(4)) < 0.3 * error(Interpolation::Bilinear));
+
+    // Resolutions without an integer ratio, and a domain with the same
+    // corners but a straight outer side
+    assert_eq!(u_fine.restrict(&Arc::new(Domain::quarter_annulus(8, 7)), Restriction::Injection).err(), Some(TransferError::Ratio{fine: 17, coarse: 8}));
+    let chord = Arc::new(Domain::new([
+        Box::new(StraightLine::<f32>::from(1_f32, 0_f32, 1_f32, 0_f32, 0_f32, 1_f32)),
+        Box::new(StraightLine::<f32>::from(-2_f32, 2_f32, 2_f32, 0_f32, 0_f32, 1_f32)),
+        Box::new(StraightLine::<f32>::from(0_f32, 1_f32, 0_f32, 1_f32, 0_f32, 1_f32)),
+        Box::new(CircleArc::from(1_f32, Point::from(0_f32, 0_f32), 0_f32, PI / 2_f32)),
+    ], 9, 7));
+    assert_eq!(u_fine.restrict(&chord, Restriction::Injection).err(), Some(TransferError::DomainMismatch));
+    assert_eq!(u_coarse.prolongate(&chord, Interpolation::Bilinear).err(), Some(TransferError::DomainMismatch));
+}
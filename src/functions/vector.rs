@@ -0,0 +1,268 @@
+use crate::functions::{GridFunction, DiffDirection, DomainMismatch};
+use crate::geometry::domain::Domain;
+use crate::geometry::metric::Metric;
+
+use byteorder::WriteBytesExt;
+use byteorder::LittleEndian;
+
+use nalgebra::DVector;
+
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use std::sync::Arc;
+
+use std::fs::File;
+use std::io::Write;
+
+/// Vector fields with an x- and a y-component on the same domain
+#[derive(Clone)]
+pub struct VectorGridFunction<T> {
+    x: GridFunction<T>,
+    y: GridFunction<T>,
+}
+
+impl VectorGridFunction<f32> {
+    /// Creates the 0-field on `domain`
+    pub fn new(domain: &Arc<Domain<f32>>) -> VectorGridFunction<f32> {
+        VectorGridFunction{x: GridFunction::new(domain), y: GridFunction::new(domain)}
+    }
+
+    /// Creates a field from the components `x` and `y`, which need to be
+    /// defined on the same domain. Derivatives use the order of `x`.
+    pub fn from(x: GridFunction<f32>, y: GridFunction<f32>) -> Result<VectorGridFunction<f32>, DomainMismatch> {
+        x.check_domain(&y)?;
+        let y = y.with_order(x.order);
+        Ok(VectorGridFunction{x, y})
+    }
+
+    /// Creates a field from `fnc`, returning the components at (x,y), on `domain`
+    pub fn from_fnc(domain: &Arc<Domain<f32>>, fnc: &dyn Fn(f32, f32) -> [f32; 2]) -> VectorGridFunction<f32> {
+        let size = domain.get_n() as usize * domain.get_m() as usize;
+        let m = domain.get_m() as usize;
+        let values: Vec<[f32; 2]> = (0..size).map(|k| {
+            let xy = domain.get_xy(k / m, k % m);
+            fnc(xy.get_x(), xy.get_y())
+        }).collect();
+        VectorGridFunction{
+            x: GridFunction::from_vector(domain, DVector::from_iterator(size, values.iter().map(|v| v[0]))),
+            y: GridFunction::from_vector(domain, DVector::from_iterator(size, values.iter().map(|v| v[1]))),
+        }
+    }
+
+    /// The x-component
+    pub fn get_x(&self) -> &GridFunction<f32> {
+        &self.x
+    }
+
+    /// The y-component
+    pub fn get_y(&self) -> &GridFunction<f32> {
+        &self.y
+    }
+
+    /// Splits the field into its x- and y-components
+    pub fn into_components(self) -> [GridFunction<f32>; 2] {
+        [self.x, self.y]
+    }
+
+    /// Calculates u·v at each point
+    pub fn dot(&self, other: &VectorGridFunction<f32>) -> Result<GridFunction<f32>, DomainMismatch> {
+        (&self.x * &other.x)? + (&self.y * &other.y)?
+    }
+
+    /// Calculates |u| at each point
+    pub fn magnitude(&self) -> GridFunction<f32> {
+        self.x.zip_map(&self.y, &|u, v| u.hypot(v)).expect("Components are defined on the same domain")
+    }
+
+    /// Calculates the divergence ∂u/∂x + ∂v/∂y in the conservative form
+    /// (∂(y_η u - x_η v)/∂ξ + ∂(x_ξ v - y_ξ u)/∂η) / J
+    pub fn divergence(&self) -> GridFunction<f32> {
+        self.transformed_derivative(&|u, v, metric| {
+            [metric.y_η * u - metric.x_η * v, metric.x_ξ * v - metric.y_ξ * u]
+        })
+    }
+
+    /// Calculates the scalar curl ∂v/∂x - ∂u/∂y in the conservative form
+    /// (∂(x_η u + y_η v)/∂ξ - ∂(x_ξ u + y_ξ v)/∂η) / J
+    pub fn curl(&self) -> GridFunction<f32> {
+        self.transformed_derivative(&|u, v, metric| {
+            [metric.x_η * u + metric.y_η * v, -metric.x_ξ * u - metric.y_ξ * v]
+        })
+    }
+
+    /// Saves the field to `location`.
+    ///
+    /// The first two bytes are the `n` and `m` values of the domain, and the
+    /// remaining are the (x,y) components at the gridpoints in the order
+    /// i*m+j, as little endian f32 pairs like the points of `save_grid`.
+    pub fn save_function(&self, location: &str) -> std::io::Result<()> {
+        let mut file = File::create(location)?;
+        file.write_all(&[self.x.n])?;
+        file.write_all(&[self.x.m])?;
+        for k in 0..(self.x.n as usize * self.x.m as usize) {
+            file.write_f32::<LittleEndian>(self.x.values[k])?;
+            file.write_f32::<LittleEndian>(self.y.values[k])?;
+        }
+        Ok(())
+    }
+
+    /// Applies `f` to each value of both components
+    fn map(&self, f: &dyn Fn(f32) -> f32) -> VectorGridFunction<f32> {
+        VectorGridFunction{x: self.x.map(f), y: self.y.map(f)}
+    }
+
+    /// Combines the components with those of `other` by `f`, if both are
+    /// defined on the same domain
+    fn zip_map(&self, other: &VectorGridFunction<f32>, f: &dyn Fn(f32, f32) -> f32) -> Result<VectorGridFunction<f32>, DomainMismatch> {
+        self.x.check_domain(&other.x)?;
+        Ok(VectorGridFunction{x: self.x.zip_map(&other.x, f)?, y: self.y.zip_map(&other.y, f)?})
+    }
+
+    /// Calculates (∂F_ξ/∂ξ + ∂F_η/∂η) / J, where `flux(u, v, metric)` gives
+    /// [F_ξ, F_η] from the components and the metric terms at a point
+    fn transformed_derivative(&self, flux: &dyn Fn(f32, f32, Metric) -> [f32; 2]) -> GridFunction<f32> {
+        let domain = &self.x.domain;
+        let order = self.x.order;
+        let fluxes = |i: usize, j: usize| -> [f32; 2] {
+            flux(self.x.get_value(i, j), self.y.get_value(i, j), domain.get_metric_of_order(i, j, order))
+        };
+        let m = self.x.m as usize;
+        let size = self.x.n as usize * m;
+        let values = DVector::from_iterator(size, (0..size).map(|k| {
+            let (i, j) = (k / m, k % m);
+            let f_ξ = self.x.partial_derivative_of_fnc(&|i, j| fluxes(i, j)[0], DiffDirection::ξ, i, j);
+            let f_η = self.x.partial_derivative_of_fnc(&|i, j| fluxes(i, j)[1], DiffDirection::η, i, j);
+            (f_ξ + f_η) / domain.get_metric_of_order(i, j, order).jacobian
+        }));
+        GridFunction::from_vector(domain, values).with_order(order)
+    }
+}
+
+impl GridFunction<f32> {
+    /// Calculates the gradient (∂u/∂x, ∂u/∂y)
+    pub fn grad(&self) -> VectorGridFunction<f32> {
+        let [x, y] = self.pd_xy();
+        VectorGridFunction{x, y}
+    }
+}
+
+/// Implements `$trait` between owned and borrowed fields
+macro_rules! field_operator {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<VectorGridFunction<f32>> for VectorGridFunction<f32> {
+            type Output = Result<VectorGridFunction<f32>, DomainMismatch>;
+            fn $method(self, other: VectorGridFunction<f32>) -> Self::Output {
+                self.zip_map(&other, &|u, v| u $op v)
+            }
+        }
+
+        impl $trait<&VectorGridFunction<f32>> for VectorGridFunction<f32> {
+            type Output = Result<VectorGridFunction<f32>, DomainMismatch>;
+            fn $method(self, other: &VectorGridFunction<f32>) -> Self::Output {
+                self.zip_map(other, &|u, v| u $op v)
+            }
+        }
+
+        impl $trait<VectorGridFunction<f32>> for &VectorGridFunction<f32> {
+            type Output = Result<VectorGridFunction<f32>, DomainMismatch>;
+            fn $method(self, other: VectorGridFunction<f32>) -> Self::Output {
+                self.zip_map(&other, &|u, v| u $op v)
+            }
+        }
+
+        impl $trait<&VectorGridFunction<f32>> for &VectorGridFunction<f32> {
+            type Output = Result<VectorGridFunction<f32>, DomainMismatch>;
+            fn $method(self, other: &VectorGridFunction<f32>) -> Self::Output {
+                self.zip_map(other, &|u, v| u $op v)
+            }
+        }
+    };
+}
+
+/// Implements `$trait` with a scalar on the right
+macro_rules! field_scalar_operator {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<f32> for VectorGridFunction<f32> {
+            type Output = VectorGridFunction<f32>;
+            fn $method(self, scalar: f32) -> Self::Output {
+                self.map(&|u| u $op scalar)
+            }
+        }
+
+        impl $trait<f32> for &VectorGridFunction<f32> {
+            type Output = VectorGridFunction<f32>;
+            fn $method(self, scalar: f32) -> Self::Output {
+                self.map(&|u| u $op scalar)
+            }
+        }
+    };
+}
+
+field_operator!(Add, add, +);
+field_operator!(Sub, sub, -);
+
+field_scalar_operator!(Mul, mul, *);
+field_scalar_operator!(Div, div, /);
+
+impl Mul<VectorGridFunction<f32>> for f32 {
+    type Output = VectorGridFunction<f32>;
+    fn mul(self, field: VectorGridFunction<f32>) -> Self::Output {
+        field.map(&|u| self * u)
+    }
+}
+
+impl Mul<&VectorGridFunction<f32>> for f32 {
+    type Output = VectorGridFunction<f32>;
+    fn mul(self, field: &VectorGridFunction<f32>) -> Self::Output {
+        field.map(&|u| self * u)
+    }
+}
+
+impl Neg for VectorGridFunction<f32> {
+    type Output = VectorGridFunction<f32>;
+    fn neg(self) -> Self::Output {
+        self.map(&|u| -u)
+    }
+}
+
+impl Neg for &VectorGridFunction<f32> {
+    type Output = VectorGridFunction<f32>;
+    fn neg(self) -> Self::Output {
+        self.map(&|u| -u)
+    }
+}
+
+#[test]
+fn test_vector_operators_on_annulus() {
+    let domain = Arc::new(Domain::quarter_annulus(31, 21));
+    let u = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x*x*y);
+    let grad = u.grad();
+    let swirl = VectorGridFunction::from_fnc(&domain, &|x: f32, y: f32| [x - y, x + y]);
+    let div = swirl.divergence();
+    let curl = swirl.curl();
+    let curl_of_grad = grad.curl();
+    let speed = swirl.magnitude();
+    let work = swirl.dot(&grad).unwrap();
+    for i in 0..31 {
+        for j in 0..21 {
+            let xy = domain.get_xy(i, j);
+            let (x, y) = (xy.get_x(), xy.get_y());
+            assert!((grad.get_x().get_value(i, j) - 2_f32*x*y).abs() < 1e-2 * (1_f32 + 2_f32*x*y));
+            assert!((grad.get_y().get_value(i, j) - x*x).abs() < 1e-2 * (1_f32 + x*x));
+            assert!((div.get_value(i, j) - 2_f32).abs() < 1e-2);
+            assert!((curl.get_value(i, j) - 2_f32).abs() < 1e-2);
+            assert!(curl_of_grad.get_value(i, j).abs() < 5e-2);
+            assert!((speed.get_value(i, j) - (2_f32*(x*x + y*y)).sqrt()).abs() < 1e-5);
+            let exact_work = (x - y) * grad.get_x().get_value(i, j) + (x + y) * grad.get_y().get_value(i, j);
+            assert!((work.get_value(i, j) - exact_work).abs() < 1e-4);
+        }
+    }
+    let difference = (swirl * 2_f32 - VectorGridFunction::from_fnc(&domain, &|x: f32, y: f32| [x, y])).unwrap().into_components();
+    assert!((difference[0].get_value(3, 4) - (domain.get_xy(3, 4).get_x() - 2_f32*domain.get_xy(3, 4).get_y())).abs() < 1e-5);
+
+    // Borrowed operands, negation and scalars agree with the owned ones
+    let zero = ((-&grad + &grad.clone()).unwrap() - (0.5 * &grad - &grad / 2_f32).unwrap()).unwrap();
+    assert!(zero.magnitude().values.iter().all(|v| *v == 0_f32));
+    let other = VectorGridFunction::new(&Arc::new(Domain::quarter_annulus(31, 21)));
+    assert_eq!((&grad - &other).err(), Some(DomainMismatch));
+    assert_eq!((grad + other).err(), Some(DomainMismatch));
+}
@@ -1,8 +1,13 @@
-use crate::numerical_methods::asymptotic_simpsons::asi;
-use crate::numerical_methods::newton::newton;
+use crate::numerical_methods::root_finding::newton_bracketed;
+use crate::numerical_methods::quadrature::{self, Quadrature, QuadratureResult};
 use crate::geometry::point::Point;
 use crate::duals;
 use crate::duals::DualNumber;
+use crate::duals::complex_step;
+use crate::duals::jet::Jet;
+use crate::intervals::Interval;
+
+use num::complex::Complex;
 
 use byteorder::WriteBytesExt;
 use byteorder::LittleEndian;
@@ -10,21 +15,38 @@ use byteorder::LittleEndian;
 use std::fs::File;
 use std::io::Write;
 
+/// Method used to take derivatives of the curve parametrizations
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Differentiation {
+    /// Automatic differentiation with `DualNumber`, see `duals::diff`
+    Dual,
+    /// Complex-step differentiation, see `complex_step::diff`
+    ComplexStep,
+}
+
 /// General curves where a curve needs
 /// an implementation of user parametrized x/y and 
 /// dx/dy getters.
-pub trait Curves<T> {
+///
+/// Curves are `Send` and `Sync` so that domains can be shared between threads.
+pub trait Curves<T>: Send + Sync {
     /// Returns the `t` that corresponds to `s` where
     /// x(t) = X(s), where x(t) is the curve parametrized from
     /// 0->1 and X(s) is the curve in user coordinates.
     fn find_s(&self, s: f32) -> f32 {
+        self.find_s_with(s, Quadrature::Simpson)
+    }
+    /// `find_s` where the curve lengths are integrated with quadrature `rule`
+    fn find_s_with(&self, s: f32, rule: Quadrature) -> f32 {
+        let length = self.integrate_with(self.get_smax(), rule).value;
         let f = |p: f32| -> f32 {
-            return self.integrate(p) - s*self.integrate(self.get_smax()) 
+            self.integrate_with(p, rule).value - s*length
         };
         let df = |p: f32| -> f32 {
             return self.integrand(p)
         };
-        return newton(&f, &df, 0_f32)
+        let (smin, smax) = (self.get_smin(), self.get_smax());
+        newton_bracketed(&f, &df, smin, smax, smin + s*(smax - smin)).root
     }
     /// Returns the value of the integrand for the curve length at `s`.
     fn integrand(&self, s: f32) -> f32 {
@@ -32,10 +54,15 @@ pub trait Curves<T> {
     }
     /// Returns the length of the curve from `get_smin()` to `s`.
     fn integrate(&self, s: f32) -> f32 {
+        self.integrate_with(s, Quadrature::Simpson).value
+    }
+    /// Returns the length of the curve from `get_smin()` to `s` using
+    /// quadrature `rule`, together with an error estimate.
+    fn integrate_with(&self, s: f32, rule: Quadrature) -> QuadratureResult {
         let f = |p: f32| -> f32 {
             self.integrand(p)
         };
-        return asi(&f, self.get_smin(), s)
+        quadrature::integrate(&f, self.get_smin(), s, rule)
     }
     /// The point in 2D space on the curve at `t`
     fn xy(&self, t: f32) -> Point<f32> {
@@ -76,6 +103,155 @@ pub trait Curves<T> {
         };
         duals::diff(&ys, s)
     }
+    /// Calculates the x-derivative of the curve at `s` with the
+    /// differentiation method `method`
+    fn dxs_with(&self, s: f32, method: Differentiation) -> f32 {
+        match method {
+            Differentiation::Dual => self.dxs(s),
+            Differentiation::ComplexStep => {
+                let xs = |s: Complex<f32>| -> Complex<f32> {
+                    self.xs_complex(s)
+                };
+                complex_step::diff(&xs, s)
+            }
+        }
+    }
+    /// Calculates the y-derivative of the curve at `s` with the
+    /// differentiation method `method`
+    fn dys_with(&self, s: f32, method: Differentiation) -> f32 {
+        match method {
+            Differentiation::Dual => self.dys(s),
+            Differentiation::ComplexStep => {
+                let ys = |s: Complex<f32>| -> Complex<f32> {
+                    self.ys_complex(s)
+                };
+                complex_step::diff(&ys, s)
+            }
+        }
+    }
+    /// Curve parametrization for x on a `Jet` to be able to take
+    /// derivatives of any order. By default it's the order 1 jet from
+    /// `xs`, so curves need to override it for higher orders, such as
+    /// with `scalar_parametrizations!`.
+    fn xs_jet(&self, s: Jet<f32>) -> Jet<f32> {
+        assert!(s.get_order() <= 1, "The curve has no parametrization for jets of order {}", s.get_order());
+        Jet::from_dual(self.xs(s.to_dual())).truncate(s.get_order())
+    }
+    /// Curve parametrization for y on a `Jet`, see `xs_jet`
+    fn ys_jet(&self, s: Jet<f32>) -> Jet<f32> {
+        assert!(s.get_order() <= 1, "The curve has no parametrization for jets of order {}", s.get_order());
+        Jet::from_dual(self.ys(s.to_dual())).truncate(s.get_order())
+    }
+    /// Curve parametrization for x on complex numbers, to be able to take
+    /// derivatives with a complex step. By default it's the first order
+    /// expansion `x(re) + i im x'(re)` from `xs`, which is what a complex
+    /// step sees, so `Differentiation::ComplexStep` falls back to the dual
+    /// numbers. Black-box parametrizations go in a `ComplexCurve` instead.
+    fn xs_complex(&self, s: Complex<f32>) -> Complex<f32> {
+        let x = self.xs(DualNumber::from(s.re, s.im));
+        Complex::new(x.get_a(), x.get_b())
+    }
+    /// Curve parametrization for y on complex numbers, see `xs_complex`
+    fn ys_complex(&self, s: Complex<f32>) -> Complex<f32> {
+        let y = self.ys(DualNumber::from(s.re, s.im));
+        Complex::new(y.get_a(), y.get_b())
+    }
+    /// Curve parametrization for x on an `Interval`, enclosing every
+    /// value x takes for parameters in `s`. By default it's the entire real
+    /// line, so that curves without it may intersect anything.
+    fn xs_interval(&self, _s: Interval<f32>) -> Interval<f32> {
+        Interval::entire()
+    }
+    /// Curve parametrization for y on an `Interval`, see `xs_interval`
+    fn ys_interval(&self, _s: Interval<f32>) -> Interval<f32> {
+        Interval::entire()
+    }
+    /// Taylor coefficients of x around `s` up to order `order`, parametrized
+    /// from `get_smin()` to `get_smax()`
+    fn taylor_xs(&self, s: f32, order: usize) -> Vec<f32> {
+        self.xs_jet(Jet::variable(s, order)).get_coefficients().to_vec()
+    }
+    /// Taylor coefficients of y around `s` up to order `order`, parametrized
+    /// from `get_smin()` to `get_smax()`
+    fn taylor_ys(&self, s: f32, order: usize) -> Vec<f32> {
+        self.ys_jet(Jet::variable(s, order)).get_coefficients().to_vec()
+    }
+    /// Amount of shape parameters of the curve that the grid can be
+    /// differentiated with respect to
+    fn get_parameter_count(&self) -> usize {
+        0
+    }
+    /// Derivative of the x-parametrization with respect to shape parameter
+    /// `k`, as a function of `s` on `DualNumber` to be able to also get
+    /// the mixed derivative with respect to `s` and `k`
+    fn xs_dp(&self, _s: DualNumber<f32>, _k: usize) -> DualNumber<f32> {
+        DualNumber::real(0_f32)
+    }
+    /// Derivative of the y-parametrization with respect to shape parameter
+    /// `k`, as a function of `s` on `DualNumber` to be able to also get
+    /// the mixed derivative with respect to `s` and `k`
+    fn ys_dp(&self, _s: DualNumber<f32>, _k: usize) -> DualNumber<f32> {
+        DualNumber::real(0_f32)
+    }
+    /// Derivative of `get_smin()` with respect to shape parameter `k`
+    fn smin_dp(&self, _k: usize) -> f32 {
+        0_f32
+    }
+    /// Derivative of `get_smax()` with respect to shape parameter `k`
+    fn smax_dp(&self, _k: usize) -> f32 {
+        0_f32
+    }
+    /// Derivative of the integrand for the curve length at `s` with
+    /// respect to shape parameter `k`
+    fn integrand_dp(&self, s: f32, k: usize) -> f32 {
+        let dxs_dp = self.xs_dp(DualNumber::from(s, 1_f32), k).get_b();
+        let dys_dp = self.ys_dp(DualNumber::from(s, 1_f32), k).get_b();
+        (self.dxs(s) * dxs_dp + self.dys(s) * dys_dp) / self.integrand(s)
+    }
+    /// Derivative of the length of the curve from `get_smin()` to `s` with
+    /// respect to shape parameter `k`, with `s` held fixed
+    fn integrate_dp(&self, s: f32, k: usize) -> f32 {
+        let f = |p: f32| -> f32 {
+            self.integrand_dp(p, k)
+        };
+        quadrature::integrate(&f, self.get_smin(), s, Quadrature::Simpson).value
+            - self.integrand(self.get_smin()) * self.smin_dp(k)
+    }
+    /// Derivative of `find_s(t)` with respect to shape parameter `k`.
+    ///
+    /// `find_s` solves L(s) - t L(smax) = 0 where L is the curve length, so by
+    /// the implicit function theorem ds/dp = -(dL(s)/dp - t dL(smax)/dp) / L'(s).
+    fn find_s_dp(&self, t: f32, k: usize) -> f32 {
+        let s = self.find_s(t);
+        let length_dp = self.integrate_dp(self.get_smax(), k)
+            + self.integrand(self.get_smax()) * self.smax_dp(k);
+        -(self.integrate_dp(s, k) - t * length_dp) / self.integrand(s)
+    }
+    /// Derivative of the point `xy(t)` with respect to shape parameter `k`
+    fn xy_dp(&self, t: f32, k: usize) -> Point<f32> {
+        let s = self.find_s(t);
+        let ds_dp = self.find_s_dp(t, k);
+        Point::from(
+            self.dxs(s) * ds_dp + self.xs_dp(DualNumber::real(s), k).get_a(),
+            self.dys(s) * ds_dp + self.ys_dp(DualNumber::real(s), k).get_a(),
+        )
+    }
+    /// Bounding box of the whole curve as intervals of x and y,
+    /// taking the hull of `pieces` boxes along the parametrization
+    fn bounding_box(&self, pieces: usize) -> [Interval<f32>; 2] {
+        let boxes = self.bounding_boxes(pieces);
+        boxes.iter().skip(1).fold(boxes[0], |[x, y], [bx, by]| [x.hull(bx), y.hull(by)])
+    }
+    /// Bounding boxes of the curve split into `pieces` pieces of
+    /// equal length in the parametrization from `get_smin()` to `get_smax()`
+    fn bounding_boxes(&self, pieces: usize) -> Vec<[Interval<f32>; 2]> {
+        let (smin, smax) = (self.get_smin(), self.get_smax());
+        let s = |k: usize| -> f32 { smin + (smax - smin) * (k as f32) / (pieces as f32) };
+        (0..pieces.max(1))
+            .map(|k| Interval::from(s(k).min(s(k+1)), s(k).max(s(k+1))))
+            .map(|s| [self.xs_interval(s), self.ys_interval(s)])
+            .collect()
+    }
 
     // Needs an implmentation for structs who want this trait
     /// Minimum value for curve parametrization
@@ -89,3 +265,79 @@ pub trait Curves<T> {
     /// to automatically take derivative
     fn ys(&self, s: DualNumber<f32>) -> DualNumber<f32>;
 }
+
+/// Implements the parametrizations of `Curves` on the number types other
+/// than `f32` with the methods `$x` and `$y` of the curve, which are
+/// written once for every `Scalar`:
+///
+/// ```ignore
+/// impl Curve {
+///     fn x<S: Scalar>(&self, s: S) -> S { s.cos() }
+///     fn y<S: Scalar>(&self, s: S) -> S { s.sin() }
+/// }
+///
+/// impl Curves<f32> for Curve {
+///     scalar_parametrizations!(x, y);
+///     ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! scalar_parametrizations {
+    ($x:ident, $y:ident) => {
+        fn xs(&self, s: $crate::duals::DualNumber<f32>) -> $crate::duals::DualNumber<f32> {
+            self.$x(s)
+        }
+        fn ys(&self, s: $crate::duals::DualNumber<f32>) -> $crate::duals::DualNumber<f32> {
+            self.$y(s)
+        }
+        fn xs_jet(&self, s: $crate::duals::jet::Jet<f32>) -> $crate::duals::jet::Jet<f32> {
+            self.$x(s)
+        }
+        fn ys_jet(&self, s: $crate::duals::jet::Jet<f32>) -> $crate::duals::jet::Jet<f32> {
+            self.$y(s)
+        }
+        fn xs_interval(&self, s: $crate::intervals::Interval<f32>) -> $crate::intervals::Interval<f32> {
+            self.$x(s)
+        }
+        fn ys_interval(&self, s: $crate::intervals::Interval<f32>) -> $crate::intervals::Interval<f32> {
+            self.$y(s)
+        }
+        fn xs_complex(&self, s: $crate::duals::complex_step::Complex<f32>) -> $crate::duals::complex_step::Complex<f32> {
+            self.$x(s)
+        }
+        fn ys_complex(&self, s: $crate::duals::complex_step::Complex<f32>) -> $crate::duals::complex_step::Complex<f32> {
+            self.$y(s)
+        }
+    };
+}
+
+/// Checks whether two curves may intersect by comparing guaranteed bounding
+/// boxes of pieces of the curves, where pieces with overlapping boxes are
+/// bisected up to `depth` times.
+///
+/// Returns `false` only when the curves are certainly disjoint.
+pub fn may_intersect(a: &dyn Curves<f32>, b: &dyn Curves<f32>, depth: u8) -> bool {
+    let range = |c: &dyn Curves<f32>| -> Interval<f32> {
+        Interval::from(c.get_smin().min(c.get_smax()), c.get_smin().max(c.get_smax()))
+    };
+    may_intersect_on(a, range(a), b, range(b), depth)
+}
+
+fn may_intersect_on(
+    a: &dyn Curves<f32>, sa: Interval<f32>, b: &dyn Curves<f32>, sb: Interval<f32>, depth: u8) -> bool
+{
+    let overlap = a.xs_interval(sa).intersects(&b.xs_interval(sb))
+        && a.ys_interval(sa).intersects(&b.ys_interval(sb));
+    match (overlap, depth) {
+        (false, _) => false,
+        (true, 0) => true,
+        (true, _) => {
+            let [sa0, sa1] = sa.bisect();
+            let [sb0, sb1] = sb.bisect();
+            may_intersect_on(a, sa0, b, sb0, depth - 1)
+                || may_intersect_on(a, sa0, b, sb1, depth - 1)
+                || may_intersect_on(a, sa1, b, sb0, depth - 1)
+                || may_intersect_on(a, sa1, b, sb1, depth - 1)
+        }
+    }
+}
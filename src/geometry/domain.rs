@@ -1,11 +1,27 @@
-use crate::geometry::curves::Curves;
+use crate::geometry::curves::{Curves, may_intersect};
 use crate::geometry::point::Point;
+use crate::geometry::sensitivity::{ShapeParameter, SensitivityGrid};
+use crate::geometry::metric::{Metric, SecondMetric, compute_metric, compute_second_metric};
+use crate::numerical_methods::finite_differences::DifferenceOrder;
 
 use byteorder::WriteBytesExt;
 use byteorder::LittleEndian;
 
 use std::fs::File;
 use std::io::Write;
+use std::sync::OnceLock;
+
+/// The gridpoints on a boundary side of a domain, ordered in the direction
+/// of its boundary curve
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundarySide {
+    /// Grid indices (i,j) of the points
+    pub indices: Vec<(usize, usize)>,
+    /// Outward unit normals at the points
+    pub normals: Vec<Point<f32>>,
+    /// Arc lengths from the start of the curve to the points
+    pub arc_lengths: Vec<f32>,
+}
 
 pub struct Domain<T> {
     boundary: [Box<dyn Curves<T>>; 4],
@@ -14,6 +30,11 @@ pub struct Domain<T> {
     m: u8,
     x: Vec<f32>,
     y: Vec<f32>,
+    /// Metric terms for each of `DifferenceOrder::ALL`
+    metric: [Vec<Metric>; 3],
+    /// Second derivatives of the mapping for each of `DifferenceOrder::ALL`,
+    /// computed the first time they are needed
+    second_metric: [OnceLock<Vec<SecondMetric>>; 3],
 }
 
 impl Domain<f32> {
@@ -27,6 +48,53 @@ impl Domain<f32> {
                 // Maybe do proper error handling some other way
             }
         }
+        let (x, y) = Self::transfinite_interpolation(n, m, &boundary_directions, &|curve: usize, t: f32| {
+            boundary[curve].xy(t)
+        });
+        let metric = DifferenceOrder::ALL.map(|order| {
+            compute_metric(n.into(), m.into(), &x, &y, spacing(n), spacing(m), order)
+        });
+        let second_metric = [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+        Domain{boundary, boundary_directions, n, m, x, y, metric, second_metric}
+    }
+
+    /// Generates a domain defined by four curves together with the derivatives
+    /// of its grid coordinates with respect to each of `parameters`
+    pub fn new_with_sensitivities(
+        boundary: [Box<dyn Curves<f32>>; 4], n: u8, m: u8, parameters: &[ShapeParameter]) -> (Domain<f32>, Vec<SensitivityGrid>)
+    {
+        let domain = Domain::new(boundary, n, m);
+        let sensitivities = parameters.iter().map(|p| domain.sensitivity(*p)).collect();
+        (domain, sensitivities)
+    }
+
+    /// Derivatives of the grid coordinates with respect to the shape
+    /// parameter `parameter`.
+    ///
+    /// The boundary points are differentiated through `find_s` and the curve
+    /// length integrals, and then blended in the same way as the grid.
+    pub fn sensitivity(&self, parameter: ShapeParameter) -> SensitivityGrid {
+        assert!(
+            parameter.curve < 4 && parameter.parameter < self.boundary[parameter.curve].get_parameter_count(),
+            "Curve {} has no shape parameter {}", parameter.curve, parameter.parameter
+        );
+        let (dx, dy) = Self::transfinite_interpolation(self.n, self.m, &self.boundary_directions, &|curve: usize, t: f32| {
+            match curve == parameter.curve {
+                true => self.boundary[curve].xy_dp(t, parameter.parameter),
+                false => Point::new(),
+            }
+        });
+        SensitivityGrid::from(parameter, self.n, self.m, dx, dy)
+    }
+
+    /// Blends the boundary curves into the grid with transfinite interpolation,
+    /// where `point(curve, t)` is the point on boundary curve `curve` at `t`.
+    ///
+    /// The blend is linear in the boundary points, so it also maps boundary
+    /// sensitivities to grid sensitivities.
+    fn transfinite_interpolation(
+        n: u8, m: u8, boundary_directions: &[bool; 4], point: &dyn Fn(usize, f32) -> Point<f32>) -> (Vec<f32>, Vec<f32>)
+    {
         let mut x = vec![0_f32; (n as u16 * m as u16) as usize];
         let mut y = vec![0_f32; (n as u16 * m as u16) as usize];
 
@@ -39,45 +107,38 @@ impl Domain<f32> {
         let mut ξs = vec![0_f32; n.into()];
         let mut ηs = vec![0_f32; m.into()];
 
-        let ψ0 = |s: f32| -> f32 { 1_f32 - s };
-        let ψ1 = |s: f32| -> f32 { s };
-
-        let ξη = |s: f32, dir: bool, dir_opposite: bool| -> f32 {
-            match dir == dir_opposite {
-                true => 1_f32 - s,
-                false => s,
-            }
-        };
-
         for i in 0..n.into() {
             ξs[i] = (i as f32) / ((n as f32) - 1_f32);
-            γ0[i] = boundary[0].xy(ξs[i]);
-            γ2[i] = boundary[2].xy(ξη(ξs[i], boundary_directions[2], boundary_directions[0]));
+            γ0[i] = point(0, ξs[i]);
+            γ2[i] = point(2, along(ξs[i], boundary_directions[2], boundary_directions[0]));
         }
         for j in 0..m.into() {
             ηs[j] = (j as f32) / ((m - 1) as f32);
-            γ1[j] = boundary[1].xy(ηs[j]);
-            γ3[j] = boundary[3].xy(ξη(ηs[j], boundary_directions[1], boundary_directions[3]));
+            γ1[j] = point(1, ηs[j]);
+            γ3[j] = point(3, along(ηs[j], boundary_directions[1], boundary_directions[3]));
         }
 
+        let corners = [γ0[0], γ0[(n as usize) - 1], γ2[0], γ2[(n as usize) - 1]];
         for i in 0..n.into() {
             for j in 0..m.into() {
-                let edge_contr = 
-                    γ3[j]*ψ0(ξs[i]) +
-                    γ1[j]*ψ1(ξs[i]) +
-                    γ0[i]*ψ0(ηs[j]) +
-                    γ2[i]*ψ1(ηs[j]);
-                let corner_contr = 
-                    -γ0[0]*ψ0(ξs[i]) * ψ0(ηs[j])
-                    -γ2[0]*ψ0(ξs[i]) * ψ1(ηs[j])
-                    -γ0[(n as usize) - 1]*ψ1(ξs[i]) * ψ0(ηs[j])
-                    -γ2[(n as usize) - 1]*ψ1(ξs[i]) * ψ1(ηs[j]);
-                let xy_value = edge_contr + corner_contr;
+                let xy_value = blend(ξs[i], ηs[j], [γ0[i], γ1[j], γ2[i], γ3[j]], corners);
                 x[i*(m as usize)+j] = xy_value.get_x();
                 y[i*(m as usize)+j] = xy_value.get_y();
             }
         }
-        Domain{boundary, boundary_directions, n, m, x, y}
+        (x, y)
+    }
+
+    /// The point at reference coordinates (`ξ`,`η`) of the transfinite
+    /// interpolation of the boundary curves, which the gridpoints are
+    /// samples of. Outside of [0,1]² the blend extrapolates linearly from
+    /// the ends of the curves.
+    pub fn map_transfinite(&self, ξ: f32, η: f32) -> Point<f32> {
+        let d = &self.boundary_directions;
+        let point = |curve: usize, t: f32| -> Point<f32> { self.boundary[curve].xy(t.clamp(0_f32, 1_f32)) };
+        let corners = [point(0, 0_f32), point(0, 1_f32), point(2, along(0_f32, d[2], d[0])), point(2, along(1_f32, d[2], d[0]))];
+        let edges = [point(0, ξ), point(1, η), point(2, along(ξ, d[2], d[0])), point(3, along(η, d[1], d[3]))];
+        blend(ξ, η, edges, corners)
     }
 
     /// Checks if the curves making up the boundary ends where other curves start
@@ -105,6 +166,17 @@ impl Domain<f32> {
         return (true, boundary_directions)
     }
 
+    /// Certifies with interval arithmetic that the opposite boundary curves
+    /// don't intersect each other, bisecting pieces with overlapping bounding
+    /// boxes up to `depth` times.
+    ///
+    /// Returns `false` when it can't be certified, which doesn't mean that
+    /// the curves intersect.
+    pub fn certify_disjoint_boundaries(&self, depth: u8) -> bool {
+        !may_intersect(&*self.boundary[0], &*self.boundary[2], depth)
+            && !may_intersect(&*self.boundary[1], &*self.boundary[3], depth)
+    }
+
     /// Amount of points in x-direction for the domain
     pub fn get_n(&self) -> u8 {
         return self.n
@@ -115,6 +187,101 @@ impl Domain<f32> {
         return self.m
     }
 
+    /// Grid spacing 1/(n-1) of the reference coordinate ξ
+    pub fn get_h_ξ(&self) -> f32 {
+        spacing(self.n)
+    }
+
+    /// Grid spacing 1/(m-1) of the reference coordinate η
+    pub fn get_h_η(&self) -> f32 {
+        spacing(self.m)
+    }
+
+    /// The metric terms of the grid mapping at the gridpoint `(i,j)`, from
+    /// second order differences
+    pub fn get_metric(&self, i: usize, j: usize) -> Metric {
+        self.get_metric_of_order(i, j, DifferenceOrder::Second)
+    }
+
+    /// The metric terms at the gridpoint `(i,j)` from differences of `order`
+    pub fn get_metric_of_order(&self, i: usize, j: usize, order: DifferenceOrder) -> Metric {
+        self.metric[order.index()][i*(self.m as usize) + j]
+    }
+
+    /// The second derivatives of the grid mapping at the gridpoint `(i,j)`
+    /// from differences of `order`
+    pub fn get_second_metric_of_order(&self, i: usize, j: usize, order: DifferenceOrder) -> SecondMetric {
+        let (n, m) = (self.n, self.m);
+        let second_metric = self.second_metric[order.index()].get_or_init(|| {
+            compute_second_metric(n.into(), m.into(), &self.x, &self.y, spacing(n), spacing(m), order)
+        });
+        second_metric[i*(m as usize) + j]
+    }
+
+    /// Grid indices (i,j) of the points on boundary side `side`, numbered as
+    /// the curves of the boundary, ordered in the direction of the curve
+    pub fn side_indices(&self, side: usize) -> Vec<(usize, usize)> {
+        let (n, m) = (self.n as usize, self.m as usize);
+        let mut indices: Vec<(usize, usize)> = match side {
+            0 => (0..n).map(|i| (i, 0)).collect(),
+            1 => (0..m).map(|j| (n - 1, j)).collect(),
+            2 => (0..n).map(|i| (i, m - 1)).collect(),
+            3 => (0..m).map(|j| (0, j)).collect(),
+            _ => panic!("Side should be between 0..3"),
+        };
+        // Sides 2 and 3 are blended in from the reversed curve when it
+        // runs the same way as the opposite curve
+        let reversed = match side {
+            2 => self.boundary_directions[2] == self.boundary_directions[0],
+            3 => self.boundary_directions[3] == self.boundary_directions[1],
+            _ => false,
+        };
+        if reversed {
+            indices.reverse();
+        }
+        indices
+    }
+
+    /// The sign that turns (y_t, -x_t) outward on boundary side `side`, with
+    /// t the reference coordinate running along the side. It is outward on
+    /// the sides where t runs counterclockwise for a positively oriented grid.
+    pub fn outward_sign(&self, side: usize) -> f32 {
+        let sign = match side {
+            0 | 1 => 1_f32,
+            2 | 3 => -1_f32,
+            _ => panic!("Side should be between 0..3"),
+        };
+        sign * self.get_metric(0, 0).jacobian.signum()
+    }
+
+    /// The gridpoints of boundary side `side` ordered as `side_indices`,
+    /// with their outward normals and arc lengths
+    pub fn side(&self, side: usize) -> BoundarySide {
+        let indices = self.side_indices(side);
+        let curve = &self.boundary[side];
+        let length = curve.integrate(curve.get_smax());
+        let last = (indices.len() - 1) as f32;
+        let sign = self.outward_sign(side);
+        let mut normals = Vec::with_capacity(indices.len());
+        let mut arc_lengths = Vec::with_capacity(indices.len());
+        for (k, (i, j)) in indices.iter().enumerate() {
+            // The points are equidistant in arc length along the curve
+            let t = (k as f32) / last;
+            let s = curve.find_s(t);
+            let (dx, dy) = (curve.dxs(s), curve.dys(s));
+            let metric = self.get_metric(*i, *j);
+            let (x_t, y_t) = match side {
+                0 | 2 => (metric.x_ξ, metric.y_ξ),
+                _ => (metric.x_η, metric.y_η),
+            };
+            // The normal of the exact tangent, turned the same way as the grid normal
+            let turn = (sign * (dy * y_t + dx * x_t)).signum() / (dx * dx + dy * dy).sqrt();
+            normals.push(Point::from(turn * dy, -turn * dx));
+            arc_lengths.push(t * length);
+        }
+        BoundarySide{indices, normals, arc_lengths}
+    }
+
     /// The value on the domain at the gridpoint `(i,j)`
     pub fn get_xy(&self, i: usize, j: usize) -> Point<f32> {
         return Point::from(self.x[i*(self.m as usize) + j], self.y[i*(self.m as usize) + j])
@@ -160,3 +327,134 @@ impl Domain<f32> {
         Ok(())
     }
 }
+
+/// The parameter of a curve at `s` along its side, which is reversed when
+/// the curve runs the same way as the opposite curve with direction
+/// `dir_opposite`
+fn along(s: f32, dir: bool, dir_opposite: bool) -> f32 {
+    match dir == dir_opposite {
+        true => 1_f32 - s,
+        false => s,
+    }
+}
+
+/// Transfinite interpolation at (`ξ`,`η`) of the boundary points
+/// `edges = [γ0(ξ), γ1(η), γ2(ξ), γ3(η)]` with the corners `[γ0(0), γ0(1), γ2(0), γ2(1)]`
+fn blend(ξ: f32, η: f32, edges: [Point<f32>; 4], corners: [Point<f32>; 4]) -> Point<f32> {
+    let ψ0 = |s: f32| -> f32 { 1_f32 - s };
+    let ψ1 = |s: f32| -> f32 { s };
+    let edge_contr =
+        edges[3]*ψ0(ξ) +
+        edges[1]*ψ1(ξ) +
+        edges[0]*ψ0(η) +
+        edges[2]*ψ1(η);
+    let corner_contr =
+        -corners[0]*ψ0(ξ) * ψ0(η)
+        -corners[2]*ψ0(ξ) * ψ1(η)
+        -corners[1]*ψ1(ξ) * ψ0(η)
+        -corners[3]*ψ1(ξ) * ψ1(η);
+    edge_contr + corner_contr
+}
+
+/// Spacing of `points` equidistant points from 0 to 1
+fn spacing(points: u8) -> f32 {
+    1_f32 / ((points - 1) as f32)
+}
+
+#[cfg(test)]
+impl Domain<f32> {
+    /// The quarter annulus 1 ≤ r ≤ 2, 0 ≤ θ ≤ π/2 with n points along the
+    /// x-axis and m points along the arcs
+    pub(crate) fn quarter_annulus(n: u8, m: u8) -> Domain<f32> {
+        use crate::curve_impl::straight_line::StraightLine;
+        use crate::curve_impl::circle_arc::CircleArc;
+        use std::f32::consts::PI;
+
+        Domain::new([
+            Box::new(StraightLine::<f32>::from(1_f32, 0_f32, 1_f32, 0_f32, 0_f32, 1_f32)),
+            Box::new(CircleArc::from(2_f32, Point::from(0_f32, 0_f32), 0_f32, PI / 2_f32)),
+            Box::new(StraightLine::<f32>::from(0_f32, 1_f32, 0_f32, 1_f32, 0_f32, 1_f32)),
+            Box::new(CircleArc::from(1_f32, Point::from(0_f32, 0_f32), 0_f32, PI / 2_f32)),
+        ], n, m)
+    }
+
+    /// The unit square [0,1]²
+    pub(crate) fn unit_square(n: u8, m: u8) -> Domain<f32> {
+        use crate::curve_impl::straight_line::StraightLine;
+
+        Domain::new([
+            Box::new(StraightLine::<f32>::unit(0)),
+            Box::new(StraightLine::<f32>::unit(1)),
+            Box::new(StraightLine::<f32>::unit(2)),
+            Box::new(StraightLine::<f32>::unit(3)),
+        ], n, m)
+    }
+}
+
+#[test]
+fn test_metric_of_parallelogram() {
+    use crate::curve_impl::straight_line::StraightLine;
+
+    // (ξ,η) -> (3ξ + η, 2η), exact for the differences
+    let domain = Domain::new([
+        Box::new(StraightLine::<f32>::from(3_f32, 0_f32, 0_f32, 0_f32, 0_f32, 1_f32)),
+        Box::new(StraightLine::<f32>::from(1_f32, 2_f32, 3_f32, 0_f32, 0_f32, 1_f32)),
+        Box::new(StraightLine::<f32>::from(3_f32, 0_f32, 1_f32, 2_f32, 0_f32, 1_f32)),
+        Box::new(StraightLine::<f32>::from(1_f32, 2_f32, 0_f32, 0_f32, 0_f32, 1_f32)),
+    ], 4, 6);
+    assert_eq!((domain.get_h_ξ(), domain.get_h_η()), (1_f32 / 3_f32, 0.2));
+    let delta = 1e-4;
+    for i in 0..4 {
+        for j in 0..6 {
+            let metric = domain.get_metric(i, j);
+            assert!((metric.x_ξ - 3_f32).abs() < delta && (metric.y_ξ).abs() < delta);
+            assert!((metric.x_η - 1_f32).abs() < delta && (metric.y_η - 2_f32).abs() < delta);
+            assert!((metric.jacobian - 6_f32).abs() < delta);
+            let [ξ_x, ξ_y, η_x, η_y] = metric.inverse();
+            assert!((ξ_x - 1_f32 / 3_f32).abs() < delta && (ξ_y + 1_f32 / 6_f32).abs() < delta);
+            assert!(η_x.abs() < delta && (η_y - 0.5).abs() < delta);
+        }
+    }
+}
+
+#[test]
+fn test_domains_two_points_across() {
+    use crate::curve_impl::straight_line::StraightLine;
+
+    // Lines of two points have no second derivatives, which are zero for
+    // the affine mapping (ξ,η) -> (3ξ + η, 2η) anyway
+    for (n, m) in [(2, 5), (5, 2), (2, 2)] {
+        let domain = Domain::new([
+            Box::new(StraightLine::<f32>::from(3_f32, 0_f32, 0_f32, 0_f32, 0_f32, 1_f32)),
+            Box::new(StraightLine::<f32>::from(1_f32, 2_f32, 3_f32, 0_f32, 0_f32, 1_f32)),
+            Box::new(StraightLine::<f32>::from(3_f32, 0_f32, 1_f32, 2_f32, 0_f32, 1_f32)),
+            Box::new(StraightLine::<f32>::from(1_f32, 2_f32, 0_f32, 0_f32, 0_f32, 1_f32)),
+        ], n, m);
+        for order in DifferenceOrder::ALL {
+            for i in 0..n as usize {
+                for j in 0..m as usize {
+                    assert!((domain.get_metric_of_order(i, j, order).jacobian - 6_f32).abs() < 1e-4);
+                    let second = domain.get_second_metric_of_order(i, j, order);
+                    let terms = [second.x_ξξ, second.y_ξξ, second.x_ξη, second.y_ξη, second.x_ηη, second.y_ηη];
+                    assert!(terms.iter().all(|t| t.abs() < 1e-3));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_certify_disjoint_boundaries() {
+    use std::f32::consts;
+    use crate::curve_impl::straight_line::StraightLine;
+    use crate::curve_impl::circle_arc::CircleArc;
+
+    let square = Domain::unit_square(3, 3);
+    assert!(square.certify_disjoint_boundaries(4));
+
+    // A bulge on the bottom that reaches through the top side
+    let bulge = Box::new(CircleArc::from(1_f32, Point::from(0.5, -0.2), 0_f32, consts::PI)) as Box<dyn Curves<f32>>;
+    let top = StraightLine::<f32>::from(-1_f32, 0_f32, 1.5, 0.5, 0_f32, 1_f32);
+    assert!(may_intersect(&*bulge, &top, 8));
+    assert!(!may_intersect(&*bulge, &StraightLine::<f32>::from(-1_f32, 0_f32, 1.5, 1_f32, 0_f32, 1_f32), 8));
+}
@@ -0,0 +1,78 @@
+use crate::numerical_methods::finite_differences::{DifferenceOrder, line_derivative, line_second_derivative, mixed_derivative};
+
+/// Metric terms of the grid mapping (ξ,η) -> (x,y) at a grid point, with
+/// ξ and η running from 0 to 1 over the domain
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[allow(non_snake_case)]
+pub struct Metric {
+    pub x_ξ: f32,
+    pub x_η: f32,
+    pub y_ξ: f32,
+    pub y_η: f32,
+    /// Determinant x_ξ y_η - x_η y_ξ of the Jacobian
+    pub jacobian: f32,
+}
+
+impl Metric {
+    /// Derivatives of the reference coordinates in physical space,
+    /// as [ξ_x, ξ_y, η_x, η_y]
+    pub fn inverse(&self) -> [f32; 4] {
+        [
+            self.y_η / self.jacobian,
+            -self.x_η / self.jacobian,
+            -self.y_ξ / self.jacobian,
+            self.x_ξ / self.jacobian,
+        ]
+    }
+}
+
+/// Second derivatives of the grid mapping at a grid point, which enter the
+/// second derivatives in physical space
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SecondMetric {
+    pub x_ξξ: f32,
+    pub x_ξη: f32,
+    pub x_ηη: f32,
+    pub y_ξξ: f32,
+    pub y_ξη: f32,
+    pub y_ηη: f32,
+}
+
+/// Metric terms at every point of the `n`×`m` grid with coordinates `x`
+/// and `y` at index i*m+j, differentiated with spacings `h_ξ` and `h_η`
+/// and the stencils of `order`
+pub(crate) fn compute_metric(
+    n: usize, m: usize, x: &[f32], y: &[f32], h_ξ: f32, h_η: f32, order: DifferenceOrder) -> Vec<Metric>
+{
+    let mut metric = Vec::with_capacity(n * m);
+    for i in 0..n {
+        for j in 0..m {
+            let x_ξ = line_derivative(&|k: usize| x[k*m + j], i, n-1, h_ξ, order);
+            let y_ξ = line_derivative(&|k: usize| y[k*m + j], i, n-1, h_ξ, order);
+            let x_η = line_derivative(&|k: usize| x[i*m + k], j, m-1, h_η, order);
+            let y_η = line_derivative(&|k: usize| y[i*m + k], j, m-1, h_η, order);
+            metric.push(Metric{x_ξ, x_η, y_ξ, y_η, jacobian: x_ξ * y_η - x_η * y_ξ});
+        }
+    }
+    metric
+}
+
+/// Second derivatives of the grid mapping at every point of the grid, with
+/// the same arguments as `compute_metric`
+pub(crate) fn compute_second_metric(
+    n: usize, m: usize, x: &[f32], y: &[f32], h_ξ: f32, h_η: f32, order: DifferenceOrder) -> Vec<SecondMetric>
+{
+    let mut second_metric = Vec::with_capacity(n * m);
+    for i in 0..n {
+        for j in 0..m {
+            let ξξ = |c: &[f32]| line_second_derivative(&|k: usize| c[k*m + j], i, n-1, h_ξ, order);
+            let ηη = |c: &[f32]| line_second_derivative(&|k: usize| c[i*m + k], j, m-1, h_η, order);
+            let ξη = |c: &[f32]| mixed_derivative(&|k: usize, l: usize| c[k*m + l], i, j, (n-1, m-1), (h_ξ, h_η), order);
+            second_metric.push(SecondMetric{
+                x_ξξ: ξξ(x), x_ξη: ξη(x), x_ηη: ηη(x),
+                y_ξξ: ξξ(y), y_ξη: ξη(y), y_ηη: ηη(y),
+            });
+        }
+    }
+    second_metric
+}
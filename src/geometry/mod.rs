@@ -1,3 +1,6 @@
 pub mod curves;
 pub mod point;
 pub mod domain;
+pub mod sensitivity;
+pub mod point_location;
+pub mod metric;
@@ -0,0 +1,272 @@
+use crate::geometry::domain::Domain;
+use crate::geometry::point::Point;
+use crate::numerical_methods::newton_system::newton_system_quiet;
+use crate::numerical_methods::interpolation::{Interpolation, interpolate_grid};
+
+use nalgebra::{DMatrix, DVector};
+
+/// How far outside a cell, in local coordinates, a point may be found and
+/// still count as inside, to not lose points on the cell edges to rounding
+const TOLERANCE: f32 = 1e-4;
+
+/// How far outside the cells along a side, in cells, a point may be to be
+/// tried against the curved boundary
+const BOUNDARY_GAP: f32 = 1_f32;
+
+/// Where a physical point is in the grid of a domain
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CellLocation {
+    /// Index (i,j) of the lower left corner of the cell
+    pub cell: (usize, usize),
+    /// Coordinates in [0,1]² within the cell
+    pub local: (f32, f32),
+    /// Reference coordinates in [0,1] over the whole domain
+    pub ξ: f32,
+    pub η: f32,
+}
+
+impl Domain<f32> {
+    /// Finds the cell containing `point` and the coordinates of the point
+    /// within it, or `None` if the point is outside the domain.
+    ///
+    /// The search walks from the middle of the grid, stepping by the local
+    /// coordinates of the point under the bilinear map of the current cell,
+    /// which reaches the cell in a few steps on smooth grids. A walk that
+    /// ends at the edge of the grid may have stopped between a curved
+    /// boundary and the cells along it, so there the transfinite map of the
+    /// boundary curves is inverted instead. The cells are only scanned if
+    /// the walk goes around in circles.
+    pub fn locate(&self, point: Point<f32>) -> Option<CellLocation> {
+        let (last_i, last_j) = (self.get_n() as usize - 2, self.get_m() as usize - 2);
+        let (x, y) = (point.get_x(), point.get_y());
+        let step = |k: usize, a: f32, last: usize| -> usize {
+            match inside(a) {
+                true => k,
+                false => (k as f32 + a.floor()).clamp(0_f32, last as f32) as usize,
+            }
+        };
+        let mut cell = (last_i / 2, last_j / 2);
+        let mut visited = vec![cell];
+        while let Some((a, b)) = invert_bilinear(&self.cell_corners(cell.0, cell.1), x, y) {
+            if inside(a) && inside(b) {
+                return Some(self.cell_location(cell, (a, b)))
+            }
+            let next = (step(cell.0, a, last_i), step(cell.1, b, last_j));
+            if next == cell {
+                return self.locate_transfinite(point, cell, (a, b))
+            }
+            if visited.contains(&next) {
+                break
+            }
+            visited.push(next);
+            cell = next;
+        }
+        self.locate_by_scan(point)
+    }
+
+    /// The point with reference coordinates (`ξ`,`η`) under the grid mapping
+    /// interpolated with `method`
+    pub fn map_reference(&self, ξ: f32, η: f32, method: Interpolation) -> Point<f32> {
+        let (n, m) = (self.get_n() as usize, self.get_m() as usize);
+        let (t_ξ, t_η) = (ξ / self.get_h_ξ(), η / self.get_h_η());
+        Point::from(
+            interpolate_grid(&|i, j| self.get_xy(i, j).get_x(), n, m, t_ξ, t_η, method),
+            interpolate_grid(&|i, j| self.get_xy(i, j).get_y(), n, m, t_ξ, t_η, method),
+        )
+    }
+
+    /// Reference coordinates (ξ,η) of `point` under the grid mapping
+    /// interpolated with `method`, or `None` if the point is outside the domain.
+    ///
+    /// Every interpolation is inverted with Newton's method from the location
+    /// found by `locate`, or else from the nearest gridpoint, since the higher
+    /// order interpolations bulge out of the cells along curved boundaries.
+    /// Points of the domain that the interpolated mapping doesn't reach, such
+    /// as those between a curved side and the straight cell edges of the
+    /// bilinear mapping, keep the location from `locate`.
+    pub fn reference_coordinates(&self, point: Point<f32>, method: Interpolation) -> Option<(f32, f32)> {
+        let located = self.locate(point).map(|location| (location.ξ, location.η));
+        let start = located.unwrap_or_else(|| self.nearest_reference(point));
+        match invert_mapping(&|ξ, η| self.map_reference(ξ, η, method), point, start) {
+            Some((ξ, η)) if inside(ξ) && inside(η) => Some((ξ.clamp(0_f32, 1_f32), η.clamp(0_f32, 1_f32))),
+            _ => located,
+        }
+    }
+
+    /// `locate` for a walk that stopped at the edge of the grid in `cell`,
+    /// with the local coordinates `local` of the point outside of it
+    fn locate_transfinite(&self, point: Point<f32>, cell: (usize, usize), local: (f32, f32)) -> Option<CellLocation> {
+        let near = |a: f32| -> bool { (-BOUNDARY_GAP..=1_f32 + BOUNDARY_GAP).contains(&a) };
+        if !near(local.0) || !near(local.1) {
+            return None
+        }
+        let (last_i, last_j) = ((self.get_n() - 1) as f32, (self.get_m() - 1) as f32);
+        let start = (
+            (cell.0 as f32 + local.0.clamp(0_f32, 1_f32)) / last_i,
+            (cell.1 as f32 + local.1.clamp(0_f32, 1_f32)) / last_j,
+        );
+        let (ξ, η) = invert_mapping(&|ξ, η| self.map_transfinite(ξ, η), point, start)?;
+        if !inside(ξ) || !inside(η) {
+            return None
+        }
+        let (t_ξ, t_η) = (ξ.clamp(0_f32, 1_f32) * last_i, η.clamp(0_f32, 1_f32) * last_j);
+        let cell = ((t_ξ as usize).min(last_i as usize - 1), (t_η as usize).min(last_j as usize - 1));
+        Some(self.cell_location(cell, (t_ξ - cell.0 as f32, t_η - cell.1 as f32)))
+    }
+
+    /// `locate` by trying every cell whose bounding box contains the point
+    fn locate_by_scan(&self, point: Point<f32>) -> Option<CellLocation> {
+        let (n, m) = (self.get_n() as usize, self.get_m() as usize);
+        let (x, y) = (point.get_x(), point.get_y());
+        for i in 0..n-1 {
+            for j in 0..m-1 {
+                let corners = self.cell_corners(i, j);
+                let margin = TOLERANCE * cell_size(&corners);
+                let outside_box = corners.iter().all(|c| c.get_x() < x - margin)
+                    || corners.iter().all(|c| c.get_x() > x + margin)
+                    || corners.iter().all(|c| c.get_y() < y - margin)
+                    || corners.iter().all(|c| c.get_y() > y + margin);
+                if outside_box {
+                    continue;
+                }
+                match invert_bilinear(&corners, x, y) {
+                    Some((a, b)) if inside(a) && inside(b) => return Some(self.cell_location((i, j), (a, b))),
+                    _ => (),
+                }
+            }
+        }
+        None
+    }
+
+    /// The location at the local coordinates `local` in `cell`
+    fn cell_location(&self, cell: (usize, usize), local: (f32, f32)) -> CellLocation {
+        let (n, m) = (self.get_n() as usize, self.get_m() as usize);
+        let (a, b) = (local.0.clamp(0_f32, 1_f32), local.1.clamp(0_f32, 1_f32));
+        CellLocation{
+            cell,
+            local: (a, b),
+            ξ: (cell.0 as f32 + a) / ((n - 1) as f32),
+            η: (cell.1 as f32 + b) / ((m - 1) as f32),
+        }
+    }
+
+    /// Reference coordinates of the gridpoint nearest to `point`
+    fn nearest_reference(&self, point: Point<f32>) -> (f32, f32) {
+        let (n, m) = (self.get_n() as usize, self.get_m() as usize);
+        let distance = |k: &usize| -> f32 {
+            let xy = self.get_xy(k / m, k % m);
+            (xy.get_x() - point.get_x()).powi(2) + (xy.get_y() - point.get_y()).powi(2)
+        };
+        let nearest = (0..n*m).min_by(|k, l| distance(k).total_cmp(&distance(l))).unwrap();
+        ((nearest / m) as f32 * self.get_h_ξ(), (nearest % m) as f32 * self.get_h_η())
+    }
+
+    /// Corners of cell (i,j) in the order (i,j), (i+1,j), (i,j+1), (i+1,j+1)
+    fn cell_corners(&self, i: usize, j: usize) -> [Point<f32>; 4] {
+        [self.get_xy(i, j), self.get_xy(i+1, j), self.get_xy(i, j+1), self.get_xy(i+1, j+1)]
+    }
+}
+
+/// Is the local or reference coordinate `s` in [0,1] up to `TOLERANCE`?
+fn inside(s: f32) -> bool {
+    (-TOLERANCE..=1_f32 + TOLERANCE).contains(&s)
+}
+
+fn cell_size(corners: &[Point<f32>; 4]) -> f32 {
+    let dx = corners[3].get_x() - corners[0].get_x();
+    let dy = corners[3].get_y() - corners[0].get_y();
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// Solves `map(ξ,η) = point` with Newton's method from `start`, with the
+/// Jacobian from central differences, if it converges
+fn invert_mapping(map: &dyn Fn(f32, f32) -> Point<f32>, point: Point<f32>, start: (f32, f32)) -> Option<(f32, f32)> {
+    let f = |v: &DVector<f32>| -> DVector<f32> {
+        let p = map(v[0], v[1]);
+        DVector::from_vec(vec![p.get_x() - point.get_x(), p.get_y() - point.get_y()])
+    };
+    let δ = 1e-3_f32;
+    let jacobian = |v: &DVector<f32>| -> DMatrix<f32> {
+        let mut jacobian = DMatrix::zeros(2, 2);
+        for k in 0..2 {
+            let mut step = DVector::zeros(2);
+            step[k] = δ;
+            jacobian.set_column(k, &((f(&(v + &step)) - f(&(v - &step))) / (2_f32 * δ)));
+        }
+        jacobian
+    };
+    let result = newton_system_quiet(&f, &jacobian, DVector::from_vec(vec![start.0, start.1]));
+    match result.converged {
+        true => Some((result.x[0], result.x[1])),
+        false => None,
+    }
+}
+
+/// Solves for the local coordinates (a,b) of (x,y) under the bilinear map
+/// of the cell through `corners`, which may be outside of the cell
+fn invert_bilinear(corners: &[Point<f32>; 4], x: f32, y: f32) -> Option<(f32, f32)> {
+    let component = |k: usize| -> [f32; 4] {
+        let c = |p: &Point<f32>| -> f32 { match k { 0 => p.get_x(), _ => p.get_y() } };
+        // p(a,b) = p00 + (p10-p00) a + (p01-p00) b + (p11-p10-p01+p00) ab
+        [
+            c(&corners[0]),
+            c(&corners[1]) - c(&corners[0]),
+            c(&corners[2]) - c(&corners[0]),
+            c(&corners[3]) - c(&corners[1]) - c(&corners[2]) + c(&corners[0]),
+        ]
+    };
+    let (px, py) = (component(0), component(1));
+    let f = |v: &DVector<f32>| -> DVector<f32> {
+        let (a, b) = (v[0], v[1]);
+        DVector::from_vec(vec![
+            px[0] + px[1]*a + px[2]*b + px[3]*a*b - x,
+            py[0] + py[1]*a + py[2]*b + py[3]*a*b - y,
+        ])
+    };
+    let jacobian = |v: &DVector<f32>| -> DMatrix<f32> {
+        let (a, b) = (v[0], v[1]);
+        DMatrix::from_row_slice(2, 2, &[
+            px[1] + px[3]*b, px[2] + px[3]*a,
+            py[1] + py[3]*b, py[2] + py[3]*a,
+        ])
+    };
+    let result = newton_system_quiet(&f, &jacobian, DVector::from_vec(vec![0.5_f32, 0.5_f32]));
+    match result.converged {
+        true => Some((result.x[0], result.x[1])),
+        false => None,
+    }
+}
+
+#[test]
+fn test_locate_in_annulus() {
+    use std::f32::consts::PI;
+
+    // Quarter of the annulus 1 < r < 2, with ξ radial and η angular
+    let domain = Domain::quarter_annulus(11, 11);
+
+    let (r, θ) = (1.5_f32, 0.7_f32);
+    let location = domain.locate(Point::from(r * θ.cos(), r * θ.sin())).unwrap();
+    assert!((location.ξ - 0.5).abs() < 1e-2);
+    assert!((location.η - θ / (PI / 2_f32)).abs() < 1e-2);
+    let (i, j) = location.cell;
+    assert_eq!(i, 5);
+    assert!(location.ξ * 10_f32 >= i as f32 && location.ξ * 10_f32 <= (i + 1) as f32);
+    assert!(location.η * 10_f32 >= j as f32 && location.η * 10_f32 <= (j + 1) as f32);
+
+    // Grid points are found exactly
+    let location = domain.locate(domain.get_xy(3, 7)).unwrap();
+    assert!((location.ξ - 0.3).abs() < 1e-4 && (location.η - 0.7).abs() < 1e-4);
+    assert!(domain.locate(domain.get_xy(0, 0)).is_some());
+    assert!(domain.locate(domain.get_xy(10, 10)).is_some());
+
+    // Between the outer arc and the chord of the cell along it
+    let θ = 0.08_f32;
+    let location = domain.locate(Point::from(1.999 * θ.cos(), 1.999 * θ.sin())).unwrap();
+    assert!((location.ξ - 0.999).abs() < 1e-3 && (location.η - θ / (PI / 2_f32)).abs() < 1e-3);
+    assert_eq!(location.cell, (9, 0));
+
+    // Inside the hole, beyond the outer arc and in the wrong quadrant
+    assert!(domain.locate(Point::from(0.5, 0.5)).is_none());
+    assert!(domain.locate(Point::from(1.5, 1.5)).is_none());
+    assert!(domain.locate(Point::from(-1.5, 0.1)).is_none());
+}
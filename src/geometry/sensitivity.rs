@@ -0,0 +1,85 @@
+use crate::geometry::point::Point;
+
+use byteorder::WriteBytesExt;
+use byteorder::LittleEndian;
+
+use std::fs::File;
+use std::io::Write;
+
+/// Shape parameter `parameter` of boundary curve `curve` of a domain, see
+/// `Curves::get_parameter_count` for the parameters of each curve.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShapeParameter {
+    pub curve: usize,
+    pub parameter: usize,
+}
+
+/// Derivatives of the grid coordinates (x,y) of a domain with
+/// respect to a shape parameter of one of its boundary curves.
+pub struct SensitivityGrid {
+    parameter: ShapeParameter,
+    n: u8,
+    m: u8,
+    dx: Vec<f32>,
+    dy: Vec<f32>,
+}
+
+impl SensitivityGrid {
+    pub(crate) fn from(parameter: ShapeParameter, n: u8, m: u8, dx: Vec<f32>, dy: Vec<f32>) -> SensitivityGrid {
+        SensitivityGrid{parameter, n, m, dx, dy}
+    }
+
+    /// The shape parameter the derivatives are taken with respect to
+    pub fn get_parameter(&self) -> ShapeParameter {
+        self.parameter
+    }
+
+    /// The derivative of the gridpoint `(i,j)`
+    pub fn get_dxy(&self, i: usize, j: usize) -> Point<f32> {
+        Point::from(self.dx[i*(self.m as usize) + j], self.dy[i*(self.m as usize) + j])
+    }
+
+    /// Saves the sensitivities to `location` in the same format as
+    /// `Domain::save_grid`.
+    pub fn save_sensitivity(&self, location: &str) -> std::io::Result<()> {
+        let mut file = File::create(location)?;
+        file.write_all(&[self.n, self.m])?;
+        for (dx, dy) in self.dx.iter().zip(self.dy.iter()) {
+            file.write_f32::<LittleEndian>(*dx)?;
+            file.write_f32::<LittleEndian>(*dy)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stretched_square() {
+    use crate::geometry::domain::Domain;
+    use crate::curve_impl::straight_line::StraightLine;
+
+    // Moving the right side of the unit square to x=1+δ changes the
+    // coefficients a of the bottom, c of the right and a, c of the top side
+    let parameters = [
+        ShapeParameter{curve: 0, parameter: 0},
+        ShapeParameter{curve: 1, parameter: 2},
+        ShapeParameter{curve: 2, parameter: 0},
+        ShapeParameter{curve: 2, parameter: 2},
+    ];
+    let weights = [1_f32, 1_f32, -1_f32, 1_f32];
+    let (domain, sensitivities) = Domain::new_with_sensitivities([
+        Box::new(StraightLine::<f32>::unit(0)),
+        Box::new(StraightLine::<f32>::unit(1)),
+        Box::new(StraightLine::<f32>::unit(2)),
+        Box::new(StraightLine::<f32>::unit(3)),
+    ], 5, 4, &parameters);
+
+    let delta = 1e-5;
+    for i in 0..5 {
+        for j in 0..4 {
+            let dxy = sensitivities.iter().zip(weights.iter())
+                .fold(Point::new(), |dxy, (sensitivity, w)| dxy + sensitivity.get_dxy(i, j) * *w);
+            assert!((dxy.get_x() - domain.get_xy(i, j).get_x()).abs() < delta);
+            assert!(dxy.get_y().abs() < delta);
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use crate::duals::GetA;
+
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use std::fmt::Display;
+
+use num::Float;
+
+/// Closed intervals `[lo, hi]` of real numbers.
+///
+/// Every operation rounds its endpoints outwards, so the result of evaluating
+/// a function on intervals contains every value the function takes for
+/// arguments inside the input intervals. The arithmetic is correctly rounded,
+/// but the elementary functions rely on the accuracy of the underlying
+/// library implementation, which the standard library doesn't specify. They
+/// are widened by `ELEMENTARY_ULPS` on each side, which covers the usual libm
+/// implementations. Arguments outside of the domain of a function give the
+/// entire real line.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Interval<T> {
+    lo: T,
+    hi: T,
+}
+
+/// Ulps the elementary functions are widened by on each side
+const ELEMENTARY_ULPS: usize = 4;
+
+/// Floats that can be rounded to the neighbouring representable value
+pub trait Outward: Float {
+    fn round_down(self) -> Self;
+    fn round_up(self) -> Self;
+}
+
+impl Outward for f32 {
+    fn round_down(self) -> f32 {
+        self.next_down()
+    }
+    fn round_up(self) -> f32 {
+        self.next_up()
+    }
+}
+
+impl Outward for f64 {
+    fn round_down(self) -> f64 {
+        self.next_down()
+    }
+    fn round_up(self) -> f64 {
+        self.next_up()
+    }
+}
+
+impl<T> Interval<T> where T: Outward {
+    /// Creates the interval `[lo, hi]`
+    pub fn from(lo: T, hi: T) -> Interval<T> {
+        assert!(lo <= hi, "Interval needs lo <= hi");
+        Interval{lo, hi}
+    }
+    /// The interval containing only `x`
+    pub fn point(x: T) -> Interval<T> {
+        Interval{lo: x, hi: x}
+    }
+    /// The interval containing every real number
+    pub fn entire() -> Interval<T> {
+        Interval{lo: T::neg_infinity(), hi: T::infinity()}
+    }
+    /// Lower endpoint
+    pub fn get_lo(&self) -> T {
+        self.lo
+    }
+    /// Upper endpoint
+    pub fn get_hi(&self) -> T {
+        self.hi
+    }
+    pub fn width(&self) -> T {
+        self.hi - self.lo
+    }
+    pub fn midpoint(&self) -> T {
+        self.lo + (self.hi - self.lo) / (T::one() + T::one())
+    }
+    pub fn contains(&self, x: T) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+    /// Do the two intervals have any point in common? An interval with a NaN
+    /// endpoint has lost its bounds, so it may intersect anything.
+    pub fn intersects(&self, other: &Interval<T>) -> bool {
+        let unbounded = [self.lo, self.hi, other.lo, other.hi].iter().any(|x| x.is_nan());
+        unbounded || (self.lo <= other.hi && other.lo <= self.hi)
+    }
+    /// The smallest interval containing both intervals
+    pub fn hull(&self, other: &Interval<T>) -> Interval<T> {
+        Interval{lo: self.lo.min(other.lo), hi: self.hi.max(other.hi)}
+    }
+    /// Splits the interval at its midpoint
+    pub fn bisect(&self) -> [Interval<T>; 2] {
+        let c = self.midpoint();
+        [Interval{lo: self.lo, hi: c}, Interval{lo: c, hi: self.hi}]
+    }
+
+    /// Inverts the interval (as in doing 1/w), which is the entire real line
+    /// if the interval contains 0
+    pub fn inv(&self) -> Interval<T> {
+        match self.contains(T::zero()) {
+            true => Interval::entire(),
+            false => Interval::outward(T::one() / self.hi, T::one() / self.lo),
+        }
+    }
+    pub fn pow(&self, a: u8) -> Interval<T> {
+        self.pow_unsigned(a as u32)
+    }
+    /// Raises the interval to the integer power `n`
+    pub fn powi(&self, n: i32) -> Interval<T> {
+        match n < 0 {
+            true => self.pow_unsigned(n.unsigned_abs()).inv(),
+            false => self.pow_unsigned(n as u32),
+        }
+    }
+    /// Raises the interval to the real power `r`, which is the entire real
+    /// line unless the interval is positive
+    pub fn powf(&self, r: T) -> Interval<T> {
+        match self.lo > T::zero() {
+            true => (self.ln() * r).exp(),
+            false => Interval::entire(),
+        }
+    }
+    /// Square root of the non-negative part of the interval, which is the
+    /// entire real line if the interval has no non-negative numbers
+    pub fn sqrt(&self) -> Interval<T> {
+        match self.hi >= T::zero() {
+            true => Interval{lo: self.lo.max(T::zero()).sqrt().round_down().max(T::zero()), hi: self.hi.sqrt().round_up()},
+            false => Interval::entire(),
+        }
+    }
+    pub fn exp(&self) -> Interval<T> {
+        Interval{lo: down(self.lo.exp()).max(T::zero()), hi: up(self.hi.exp())}
+    }
+    /// Natural logarithm, which goes to -∞ if the interval reaches 0 and is
+    /// the entire real line if the interval has no positive numbers
+    pub fn ln(&self) -> Interval<T> {
+        match (self.lo > T::zero(), self.hi > T::zero()) {
+            (true, _) => Interval::widened(self.lo.ln(), self.hi.ln()),
+            (false, true) => Interval{lo: T::neg_infinity(), hi: up(self.hi.ln())},
+            (false, false) => Interval::entire(),
+        }
+    }
+    pub fn atan(&self) -> Interval<T> {
+        Interval::widened(self.lo.atan(), self.hi.atan())
+    }
+    pub fn sinh(&self) -> Interval<T> {
+        Interval::widened(self.lo.sinh(), self.hi.sinh())
+    }
+    pub fn cosh(&self) -> Interval<T> {
+        let lo = match self.contains(T::zero()) {
+            true => T::one(),
+            false => down(self.lo.abs().min(self.hi.abs()).cosh()).max(T::one()),
+        };
+        Interval{lo, hi: up(self.lo.abs().max(self.hi.abs()).cosh())}
+    }
+    pub fn tanh(&self) -> Interval<T> {
+        Interval::widened(self.lo.tanh(), self.hi.tanh())
+    }
+    pub fn cos(&self) -> Interval<T> {
+        let π = T::from(std::f64::consts::PI).unwrap();
+        self.periodic_extrema(T::cos, T::zero(), π)
+    }
+    pub fn sin(&self) -> Interval<T> {
+        let π = T::from(std::f64::consts::PI).unwrap();
+        self.periodic_extrema(T::sin, π / (T::one() + T::one()), -π / (T::one() + T::one()))
+    }
+    /// Tangent of the interval, which is the entire real line if the
+    /// interval contains a pole
+    pub fn tan(&self) -> Interval<T> {
+        let π = T::from(std::f64::consts::PI).unwrap();
+        let pole = π / (T::one() + T::one());
+        match self.contains_shifted(pole, π) || self.width() >= π {
+            true => Interval::entire(),
+            false => Interval::widened(self.lo.tan(), self.hi.tan()),
+        }
+    }
+
+    fn pow_unsigned(&self, a: u32) -> Interval<T> {
+        if a == 0 {
+            return Interval::point(T::one())
+        }
+        let p = |x: T| -> T { x.powi(a as i32) };
+        if a % 2 == 1 || self.lo >= T::zero() {
+            Interval::widened(p(self.lo), p(self.hi))
+        } else if self.hi <= T::zero() {
+            Interval::widened(p(self.hi), p(self.lo)).hull(&Interval::point(T::zero()))
+        } else {
+            Interval{lo: T::zero(), hi: up(p(self.lo).max(p(self.hi)))}
+        }
+    }
+
+    /// Encloses `[lo, hi]` after one rounding outwards on each side
+    fn outward(lo: T, hi: T) -> Interval<T> {
+        Interval{lo: lo.round_down(), hi: hi.round_up()}
+    }
+
+    /// Encloses the values `lo` and `hi` of an elementary function, see
+    /// `ELEMENTARY_ULPS`
+    fn widened(lo: T, hi: T) -> Interval<T> {
+        Interval{lo: down(lo), hi: up(hi)}
+    }
+
+    /// Does the interval contain `x + k*period` for some integer `k`? The
+    /// check is widened slightly since π is not representable.
+    fn contains_shifted(&self, x: T, period: T) -> bool {
+        let period = period.round_up();
+        let k = ((self.lo - x) / period).floor();
+        let eps = T::epsilon() * (T::one() + self.lo.abs().max(self.hi.abs()));
+        let shifted = x + k * period;
+        (shifted >= self.lo - eps && shifted <= self.hi + eps)
+            || (shifted + period >= self.lo - eps && shifted + period <= self.hi + eps)
+    }
+
+    /// Range of a 2π-periodic function in [-1,1] with maxima at
+    /// `max_at + 2πk` and minima at `min_at + 2πk`
+    fn periodic_extrema(&self, f: fn(T) -> T, max_at: T, min_at: T) -> Interval<T> {
+        let one = T::one();
+        let τ = T::from(2_f64 * std::f64::consts::PI).unwrap();
+        if self.width() >= τ {
+            return Interval{lo: -one, hi: one}
+        }
+        let (a, b) = (f(self.lo), f(self.hi));
+        let hi = match self.contains_shifted(max_at, τ) {
+            true => one,
+            false => up(a.max(b)).min(one),
+        };
+        let lo = match self.contains_shifted(min_at, τ) {
+            true => -one,
+            false => down(a.min(b)).max(-one),
+        };
+        Interval{lo, hi}
+    }
+}
+
+/// `x` rounded down by `ELEMENTARY_ULPS`
+fn down<T: Outward>(x: T) -> T {
+    (0..ELEMENTARY_ULPS).fold(x, |x, _| x.round_down())
+}
+
+/// `x` rounded up by `ELEMENTARY_ULPS`
+fn up<T: Outward>(x: T) -> T {
+    (0..ELEMENTARY_ULPS).fold(x, |x, _| x.round_up())
+}
+
+impl GetA for Interval<f32> {
+    fn get_a(&self) -> f32 {
+        self.midpoint()
+    }
+}
+
+impl GetA for Interval<f64> {
+    fn get_a(&self) -> f32 {
+        self.midpoint() as f32
+    }
+}
+
+impl<T> Display for Interval<T>
+    where T: Display
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{},{}]", self.lo, self.hi)
+    }
+}
+
+impl<T: Outward> Add for Interval<T> {
+    type Output = Interval<T>;
+    fn add(self, other: Interval<T>) -> Self::Output {
+        Interval::outward(self.lo + other.lo, self.hi + other.hi)
+    }
+}
+
+impl<T: Outward> Add<T> for Interval<T> {
+    type Output = Interval<T>;
+    fn add(self, other: T) -> Self::Output {
+        self + Interval::point(other)
+    }
+}
+
+impl<T: Outward> Sub for Interval<T> {
+    type Output = Interval<T>;
+    fn sub(self, other: Interval<T>) -> Self::Output {
+        Interval::outward(self.lo - other.hi, self.hi - other.lo)
+    }
+}
+
+impl<T: Outward> Sub<T> for Interval<T> {
+    type Output = Interval<T>;
+    fn sub(self, other: T) -> Self::Output {
+        self - Interval::point(other)
+    }
+}
+
+impl<T: Outward> Mul for Interval<T> {
+    type Output = Interval<T>;
+    fn mul(self, other: Interval<T>) -> Self::Output {
+        let products = [
+            self.lo * other.lo, self.lo * other.hi,
+            self.hi * other.lo, self.hi * other.hi,
+        ];
+        let lo = products.iter().fold(T::infinity(), |lo, p| lo.min(*p));
+        let hi = products.iter().fold(T::neg_infinity(), |hi, p| hi.max(*p));
+        Interval::outward(lo, hi)
+    }
+}
+
+impl<T: Outward> Mul<T> for Interval<T> {
+    type Output = Interval<T>;
+    fn mul(self, other: T) -> Self::Output {
+        self * Interval::point(other)
+    }
+}
+
+impl<T: Outward> Div for Interval<T> {
+    type Output = Interval<T>;
+    fn div(self, other: Interval<T>) -> Self::Output {
+        match other.contains(T::zero()) {
+            true => Interval::entire(),
+            false => {
+                let quotients = [
+                    self.lo / other.lo, self.lo / other.hi,
+                    self.hi / other.lo, self.hi / other.hi,
+                ];
+                let lo = quotients.iter().fold(T::infinity(), |lo, q| lo.min(*q));
+                let hi = quotients.iter().fold(T::neg_infinity(), |hi, q| hi.max(*q));
+                Interval::outward(lo, hi)
+            }
+        }
+    }
+}
+
+impl<T: Outward> Div<T> for Interval<T> {
+    type Output = Interval<T>;
+    fn div(self, other: T) -> Self::Output {
+        self / Interval::point(other)
+    }
+}
+
+impl<T: Outward> Neg for Interval<T> {
+    type Output = Interval<T>;
+    fn neg(self) -> Self::Output {
+        Interval{lo: -self.hi, hi: -self.lo}
+    }
+}
+
+#[test]
+fn test_interval_arithmetic() {
+    let a = Interval::from(-1_f32, 2_f32);
+    let b = Interval::from(3_f32, 4_f32);
+    let enclosed = [
+        (a + b, 2_f32, 6_f32),
+        (a - b, -5_f32, -1_f32),
+        (a * b, -4_f32, 8_f32),
+        (a / b, -1_f32 / 3_f32, 2_f32 / 3_f32),
+        (a.pow(2), 0_f32, 4_f32),
+        (-a, -2_f32, 1_f32),
+    ];
+    // The arithmetic is rounded by one ulp, powers by `ELEMENTARY_ULPS`
+    for (interval, lo, hi) in enclosed.iter() {
+        assert!(interval.contains(*lo) && interval.contains(*hi));
+        assert!((interval.get_lo() - lo).abs() < 4e-6 && (interval.get_hi() - hi).abs() < 4e-6);
+    }
+    assert_eq!(b.inv().get_hi(), (1_f32 / 3_f32).next_up());
+    assert_eq!(a.inv(), Interval::entire());
+}
+
+#[test]
+fn test_interval_functions_enclose_samples() {
+    let fncs: [(fn(Interval<f64>) -> Interval<f64>, fn(f64) -> f64); 8] = [
+        (|x| x.sin(), f64::sin),
+        (|x| x.cos(), f64::cos),
+        (|x| x.tan(), f64::tan),
+        (|x| x.exp(), f64::exp),
+        (|x| x.atan(), f64::atan),
+        (|x| x.cosh(), f64::cosh),
+        (|x| x.tanh(), f64::tanh),
+        (|x| (x * x + 1_f64).sqrt().ln(), |x| (x * x + 1_f64).sqrt().ln()),
+    ];
+    let intervals = [(-0.3, 0.2), (1.0, 2.0), (1.5, 1.6), (-4.0, -2.5), (3.0, 7.0)];
+    for (f_interval, f) in fncs.iter() {
+        for (lo, hi) in intervals.iter() {
+            let enclosure = f_interval(Interval::from(*lo, *hi));
+            for k in 0..=100 {
+                let x = lo + (hi - lo) * (k as f64) / 100_f64;
+                assert!(enclosure.contains(f(x)));
+            }
+        }
+    }
+    // The extrema of cos at 0 and π are attained inside
+    assert_eq!(Interval::from(-0.1_f64, 3.2).cos(), Interval::from(-1.0, 1.0));
+
+    // Arguments outside of the domain don't give NaN, which would compare as
+    // disjoint from everything
+    let nonpositive = Interval::from(-1_f32, 0.5);
+    assert_eq!(nonpositive.ln(), Interval::from(f32::NEG_INFINITY, 0.5_f32.ln().next_up().next_up().next_up().next_up()));
+    assert_eq!(Interval::from(-2_f32, -1_f32).ln(), Interval::entire());
+    assert_eq!(nonpositive.powf(0.5), Interval::entire());
+    assert_eq!(nonpositive.sqrt().get_lo(), 0_f32);
+    assert_eq!(Interval::from(-2_f32, -1_f32).sqrt(), Interval::entire());
+    let nan = Interval{lo: f32::NAN, hi: 1_f32};
+    assert!(nan.intersects(&Interval::point(5_f32)) && Interval::point(5_f32).intersects(&nan));
+}
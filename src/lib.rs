@@ -8,3 +8,5 @@ pub mod curve_impl;
 pub mod functions;
 
 pub mod duals;
+pub mod intervals;
+pub mod expressions;
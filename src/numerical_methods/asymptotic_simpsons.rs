@@ -1,21 +1,48 @@
-fn simpson_quad(f: &dyn Fn(f32) -> f32,a: f32,b: f32) -> f32 {
-    return (b-a)/6.0 * (f(a) + 4.0*f((a+b)/2.0) + f(b))
+use crate::numerical_methods::quadrature::QuadratureResult;
+
+/// Levels of bisection before giving up on the tolerance
+const MAX_DEPTH: u32 = 20;
+
+/// Simpson rule on [a,b] from the values `fa`, `fm`, `fb` at the ends and
+/// the midpoint
+fn simpson_quad(a: f32, b: f32, fa: f32, fm: f32, fb: f32) -> f32 {
+    (b-a)/6.0 * (fa + 4.0*fm + fb)
 }
 
-fn asi_wtol(f: &dyn Fn(f32) -> f32, a: f32, b: f32, tol: f32) -> f32 {
+/// Adaptive Simpson on [a,b] where `whole` is the Simpson rule on all of
+/// it from the values `fa`, `fm`, `fb`, which are passed down so that
+/// every level only evaluates `f` at the two new midpoints. The tolerance
+/// is split evenly between the halves and the recursion stops `depth`
+/// levels down even if the tolerance isn't met.
+fn asi_wtol(f: &dyn Fn(f32) -> f32, a: f32, b: f32, [fa, fm, fb]: [f32; 3], whole: f32, tol: f32, depth: u32) -> QuadratureResult {
     let c = (a+b)/2.0;
-    let i1 = simpson_quad(f,a,b);
-    let i2 = simpson_quad(f,a,c) + simpson_quad(f,c,b);
-    let err = (i1 - i2).abs();
-    match err < tol {
-        true => return i2,
-        false => return asi_wtol(f,a,(a+b)/2.0,tol/2.0) + asi_wtol(f,(a+b)/2.0,b,tol)
+    let (fl, fr) = (f((a+c)/2.0), f((c+b)/2.0));
+    let left = simpson_quad(a,c,fa,fl,fm);
+    let right = simpson_quad(c,b,fm,fr,fb);
+    let err = (left + right - whole).abs();
+    if err < 15.0*tol {
+        return QuadratureResult{value: left + right, error: err/15.0, evaluations: 2, converged: true}
+    }
+    if depth == 0 {
+        return QuadratureResult{value: left + right, error: err/15.0, evaluations: 2, converged: false}
+    }
+    let l = asi_wtol(f,a,c,[fa,fl,fm],left,tol/2.0,depth-1);
+    let r = asi_wtol(f,c,b,[fm,fr,fb],right,tol/2.0,depth-1);
+    QuadratureResult{
+        value: l.value + r.value,
+        error: l.error + r.error,
+        evaluations: 2 + l.evaluations + r.evaluations,
+        converged: l.converged && r.converged,
     }
 }
 
-/// Calculates the integral of closure `f` from `a` to `b`.
-pub fn asi(f: &dyn Fn(f32) -> f32, a: f32, b: f32) -> f32 {
-    return asi_wtol(f,a,b,1e-8)
+/// Adaptive Simpson with error estimate and evaluation count
+pub(crate) fn asi_result(f: &dyn Fn(f32) -> f32, a: f32, b: f32) -> QuadratureResult {
+    let values = [f(a), f((a+b)/2.0), f(b)];
+    let whole = simpson_quad(a,b,values[0],values[1],values[2]);
+    let mut result = asi_wtol(f,a,b,values,whole,1e-6,MAX_DEPTH);
+    result.evaluations += 3;
+    result
 }
 
 #[test]
@@ -23,6 +50,16 @@ fn test_asi_polynomials() {
     let x = |t: f32| -> f32 {return t};
     let x2 = |t: f32| -> f32 {return f32::powf(t,2_f32)};
     let delta: f32 = 1e-8;
-    assert!((asi(&x,0_f32,1_f32) - 0.5).abs() < delta);
-    assert!((asi(&x2,-1_f32,1_f32) - 2_f32/3_f32).abs() < delta);
+    assert!((asi_result(&x,0_f32,1_f32).value - 0.5).abs() < delta);
+    assert!((asi_result(&x2,-1_f32,1_f32).value - 2_f32/3_f32).abs() < delta);
+
+    // Every evaluation is at a new point
+    let points = std::cell::RefCell::new(Vec::new());
+    let sin = |t: f32| -> f32 { points.borrow_mut().push(t); t.sin() };
+    let result = asi_result(&sin, 0_f32, 3_f32);
+    let mut points = points.into_inner();
+    assert_eq!(result.evaluations, points.len());
+    points.sort_by(f32::total_cmp);
+    points.dedup();
+    assert_eq!(result.evaluations, points.len());
 }
@@ -0,0 +1,161 @@
+use crate::geometry::domain::Domain;
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+/// Safety factor of the Grid Convergence Index for studies with three or
+/// more grids
+const SAFETY_FACTOR: f32 = 1.25;
+
+/// Estimates from three consecutive levels of a convergence study
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RichardsonEstimate {
+    /// Observed order of convergence
+    pub order: f32,
+    /// Richardson extrapolation of the value to zero grid spacing
+    pub extrapolated: f32,
+    /// Grid Convergence Index of the finest of the three levels, as an
+    /// estimate of its relative error
+    pub gci: f32,
+}
+
+/// Reasons three levels don't give a Richardson estimate
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConvergenceError {
+    /// One of the differences between consecutive levels is zero or not
+    /// finite, so there is no observed order
+    ZeroDifference,
+    /// The differences between consecutive levels change sign
+    Oscillating,
+    /// The differences don't decrease towards the finer levels
+    Diverging,
+}
+
+impl Display for ConvergenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConvergenceError::ZeroDifference => write!(f, "Differences between the levels are zero or not finite"),
+            ConvergenceError::Oscillating => write!(f, "Differences between the levels oscillate"),
+            ConvergenceError::Diverging => write!(f, "Differences between the levels don't decrease"),
+        }
+    }
+}
+
+impl std::error::Error for ConvergenceError {}
+
+/// Values of a quantity on a sequence of grids, from the coarsest to the finest
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvergenceStudy {
+    pub resolutions: Vec<(u8, u8)>,
+    /// Representative grid spacing 1/√((n-1)(m-1)) of each level
+    pub spacings: Vec<f32>,
+    pub values: Vec<f32>,
+    /// One estimate for each three consecutive levels
+    pub estimates: Vec<Result<RichardsonEstimate, ConvergenceError>>,
+}
+
+/// Runs a convergence study of `quantity` on the domains `factory(n,m)`
+/// for each (n,m) in `resolutions`, ordered from the coarsest to the finest.
+///
+/// Refinement ratios don't need to be constant between levels, the
+/// observed order is then found by fixed point iteration.
+pub fn convergence_study(
+    factory: &dyn Fn(u8, u8) -> Domain<f32>, quantity: &dyn Fn(&Arc<Domain<f32>>) -> f32,
+    resolutions: &[(u8, u8)]) -> ConvergenceStudy
+{
+    assert!(resolutions.len() >= 3, "A convergence study needs at least three levels");
+    let spacings: Vec<f32> = resolutions.iter()
+        .map(|(n, m)| 1_f32 / (((n - 1) as f32) * ((m - 1) as f32)).sqrt())
+        .collect();
+    let values: Vec<f32> = resolutions.iter()
+        .map(|(n, m)| quantity(&Arc::new(factory(*n, *m))))
+        .collect();
+    let estimates = (0..resolutions.len() - 2)
+        .map(|k| richardson(
+            [values[k + 2], values[k + 1], values[k]],
+            [spacings[k + 2], spacings[k + 1], spacings[k]],
+        ))
+        .collect();
+    ConvergenceStudy{resolutions: resolutions.to_vec(), spacings, values, estimates}
+}
+
+/// Observed order, extrapolation and GCI from `values` on grids with
+/// `spacings`, both ordered from the finest to the coarsest. Only
+/// monotonically converging values have an estimate.
+pub fn richardson(values: [f32; 3], spacings: [f32; 3]) -> Result<RichardsonEstimate, ConvergenceError> {
+    let [f1, f2, f3] = values;
+    let r21 = spacings[1] / spacings[0];
+    let r32 = spacings[2] / spacings[1];
+    let ε21 = f2 - f1;
+    let ε32 = f3 - f2;
+    let quotient = ε32 / ε21;
+    if !quotient.is_finite() || quotient == 0_f32 {
+        return Err(ConvergenceError::ZeroDifference);
+    }
+    if quotient < 0_f32 {
+        return Err(ConvergenceError::Oscillating);
+    }
+    let mut order = quotient.ln() / r21.ln();
+    for _ in 0..50 {
+        let q = ((r21.powf(order) - 1_f32) / (r32.powf(order) - 1_f32)).ln();
+        let next = (quotient.ln() + q).abs() / r21.ln();
+        if (next - order).abs() < 1e-6 {
+            order = next;
+            break;
+        }
+        order = next;
+    }
+    let ratio = r21.powf(order);
+    if quotient <= 1_f32 || !(ratio > 1_f32 && ratio.is_finite()) {
+        return Err(ConvergenceError::Diverging);
+    }
+    Ok(RichardsonEstimate{
+        order,
+        extrapolated: (ratio * f1 - f2) / (ratio - 1_f32),
+        gci: SAFETY_FACTOR * (ε21 / f1).abs() / (ratio - 1_f32),
+    })
+}
+
+#[test]
+fn test_richardson_known_order() {
+    // Exactly second order with the limit 1
+    let quantity = |domain: &Arc<Domain<f32>>| -> f32 {
+        let h = 1_f32 / ((domain.get_n() - 1) as f32);
+        1_f32 + 0.5 * h * h
+    };
+    for resolutions in [[(5, 5), (9, 9), (17, 17)], [(5, 5), (8, 8), (13, 13)]] {
+        let study = convergence_study(&Domain::unit_square, &quantity, &resolutions);
+        let estimate = study.estimates[0].unwrap();
+        assert!((estimate.order - 2_f32).abs() < 1e-2);
+        assert!((estimate.extrapolated - 1_f32).abs() < 1e-4);
+        assert!(estimate.gci >= (study.values[2] - 1_f32).abs());
+    }
+
+    let spacings = [0.25_f32, 0.5, 1_f32];
+    assert_eq!(richardson([1_f32, 1_f32, 1.5], spacings), Err(ConvergenceError::ZeroDifference));
+    assert_eq!(richardson([1_f32, 1.5, 1.5], spacings), Err(ConvergenceError::ZeroDifference));
+    assert_eq!(richardson([1_f32, 1.5, f32::NAN], spacings), Err(ConvergenceError::ZeroDifference));
+    assert_eq!(richardson([1_f32, 1.5, 1.25], spacings), Err(ConvergenceError::Oscillating));
+    assert_eq!(richardson([1_f32, 2_f32, 2.5], spacings), Err(ConvergenceError::Diverging));
+}
+
+#[test]
+fn test_convergence_of_annulus_area() {
+    use crate::functions::GridFunction;
+    use crate::functions::integration::GridQuadrature;
+    use std::f32::consts::PI;
+
+    let area = |domain: &Arc<Domain<f32>>| -> f32 {
+        GridFunction::from_fnc(domain, &|_: f32, _: f32| 1_f32).integrate(GridQuadrature::Trapezoid)
+    };
+    let study = convergence_study(&Domain::quarter_annulus, &area, &[(9, 9), (17, 17), (33, 33), (65, 65)]);
+    let exact = 3_f32 * PI / 4_f32;
+    // Second order, but the f32 boundary points make the observed order noisy
+    for estimate in study.estimates.iter() {
+        let order = estimate.unwrap().order;
+        assert!(order > 1.4 && order < 2.5);
+    }
+    let last = study.estimates[1].unwrap();
+    assert!((last.extrapolated - exact).abs() < (study.values[3] - exact).abs());
+    assert!(last.gci >= ((study.values[3] - exact) / exact).abs());
+}
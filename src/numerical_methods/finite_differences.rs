@@ -0,0 +1,140 @@
+/// Order of accuracy of finite difference stencils, both in the interior
+/// and in the one sided closures at the ends of grid lines
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DifferenceOrder {
+    #[default]
+    Second,
+    Fourth,
+    Sixth,
+}
+
+impl DifferenceOrder {
+    pub const ALL: [DifferenceOrder; 3] = [DifferenceOrder::Second, DifferenceOrder::Fourth, DifferenceOrder::Sixth];
+
+    /// The order as a number
+    pub fn get_order(&self) -> usize {
+        match self {
+            DifferenceOrder::Second => 2,
+            DifferenceOrder::Fourth => 4,
+            DifferenceOrder::Sixth => 6,
+        }
+    }
+
+    /// Position of the order in `ALL`
+    pub(crate) fn index(&self) -> usize {
+        self.get_order() / 2 - 1
+    }
+}
+
+/// Weights of the `derivative`:th derivative at `z` from values at the
+/// distinct points `x`, with Fornberg's recursion
+pub fn fornberg_weights(z: f64, x: &[f64], derivative: usize) -> Vec<f64> {
+    let points = x.len();
+    assert!(points > derivative, "{} points can't approximate derivative {}", points, derivative);
+    // c[j][d] is the weight of x[j] for derivative d
+    let mut c = vec![vec![0_f64; derivative + 1]; points];
+    c[0][0] = 1_f64;
+    let mut c1 = 1_f64;
+    let mut c4 = x[0] - z;
+    for i in 1..points {
+        let highest = i.min(derivative);
+        let mut c2 = 1_f64;
+        let c5 = c4;
+        c4 = x[i] - z;
+        for j in 0..i {
+            let c3 = x[i] - x[j];
+            c2 *= c3;
+            if j == i - 1 {
+                for d in (1..=highest).rev() {
+                    c[i][d] = c1 * ((d as f64) * c[i-1][d-1] - c5 * c[i-1][d]) / c2;
+                }
+                c[i][0] = -c1 * c5 * c[i-1][0] / c2;
+            }
+            for d in (1..=highest).rev() {
+                c[j][d] = (c4 * c[j][d] - (d as f64) * c[j][d-1]) / c3;
+            }
+            c[j][0] = c4 * c[j][0] / c3;
+        }
+        c1 = c2;
+    }
+    c.iter().map(|weights| weights[derivative]).collect()
+}
+
+/// Stencil of the `derivative`:th derivative at index `k` of a grid line
+/// with indices `0..=last` and unit spacing, as the first index and the
+/// weights of the consecutive values from there.
+///
+/// The stencil is central where it fits and shifted inwards at the ends,
+/// with one more point to keep the order. Lines that are too short for
+/// the order use all their points, and lines with no more points than the
+/// derivative get zero weights.
+pub fn stencil(derivative: usize, order: DifferenceOrder, k: usize, last: usize) -> (usize, Vec<f32>) {
+    if last < derivative {
+        return (0, vec![0_f32; last + 1])
+    }
+    let p = order.get_order();
+    let half = (p + derivative - 1) / 2;
+    let (start, end) = if k >= half && k + half <= last {
+        (k - half, k + half)
+    } else {
+        let width = (p + derivative - 1).min(last);
+        match k < half {
+            true => (0, width),
+            false => (last - width, last),
+        }
+    };
+    let x: Vec<f64> = (start..=end).map(|l| (l as f64) - (k as f64)).collect();
+    let weights = fornberg_weights(0_f64, &x, derivative).iter().map(|w| *w as f32).collect();
+    (start, weights)
+}
+
+/// Derivative at index `k` of the values `f(0)..=f(last)` on a grid line
+/// with spacing `h`, using the stencils of `order`.
+///
+/// Lines of only two points get the first order difference.
+pub fn line_derivative(f: &dyn Fn(usize) -> f32, k: usize, last: usize, h: f32, order: DifferenceOrder) -> f32 {
+    let (start, weights) = stencil(1, order, k, last);
+    weights.iter().enumerate().map(|(l, w)| w * f(start + l)).sum::<f32>() / h
+}
+
+/// Second derivative at index `k` of the values `f(0)..=f(last)` on a grid
+/// line with spacing `h`, using the compact stencils of `order`
+pub fn line_second_derivative(f: &dyn Fn(usize) -> f32, k: usize, last: usize, h: f32, order: DifferenceOrder) -> f32 {
+    let (start, weights) = stencil(2, order, k, last);
+    weights.iter().enumerate().map(|(l, w)| w * f(start + l)).sum::<f32>() / (h * h)
+}
+
+/// Mixed derivative at `(i,j)` of the values `f(i,j)` on a grid with the
+/// last indices `last` and spacings `h` in the directions (ξ,η), as the
+/// tensor product of the first derivative stencils of `order`
+pub fn mixed_derivative(
+    f: &dyn Fn(usize, usize) -> f32, i: usize, j: usize, last: (usize, usize), h: (f32, f32), order: DifferenceOrder) -> f32
+{
+    let ((last_ξ, last_η), (h_ξ, h_η)) = (last, h);
+    let (start, weights) = stencil(1, order, j, last_η);
+    weights.iter().enumerate()
+        .map(|(l, w)| w * line_derivative(&|k: usize| f(k, start + l), i, last_ξ, h_ξ, order))
+        .sum::<f32>() / h_η
+}
+
+#[test]
+fn test_line_derivative_polynomials() {
+    // Each order is exact for polynomials of its degree, also at the ends
+    let h = 0.125_f32;
+    for order in DifferenceOrder::ALL {
+        let p = order.get_order() as i32;
+        let f = |k: usize| -> f32 { ((k as f32) * h).powi(p) };
+        for k in 0..=10 {
+            let x = (k as f32) * h;
+            let exact = (p as f32) * x.powi(p - 1);
+            assert!((line_derivative(&f, k, 10, h, order) - exact).abs() < 1e-3 * (1_f32 + exact));
+        }
+    }
+    assert_eq!(stencil(1, DifferenceOrder::Fourth, 5, 10), (3, vec![1_f32/12_f32, -2_f32/3_f32, 0_f32, 2_f32/3_f32, -1_f32/12_f32]));
+    assert_eq!(stencil(1, DifferenceOrder::Second, 0, 10), (0, vec![-1.5, 2_f32, -0.5]));
+    assert_eq!(stencil(1, DifferenceOrder::Sixth, 1, 1), (0, vec![-1_f32, 1_f32]));
+    assert_eq!(stencil(2, DifferenceOrder::Second, 5, 10), (4, vec![1_f32, -2_f32, 1_f32]));
+    assert_eq!(stencil(2, DifferenceOrder::Second, 0, 10), (0, vec![2_f32, -5_f32, 4_f32, -1_f32]));
+    assert_eq!(stencil(2, DifferenceOrder::Sixth, 1, 1), (0, vec![0_f32, 0_f32]));
+    assert_eq!(stencil(2, DifferenceOrder::Fourth, 0, 2), (0, vec![1_f32, -2_f32, 1_f32]));
+}
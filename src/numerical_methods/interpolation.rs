@@ -0,0 +1,69 @@
+use crate::numerical_methods::finite_differences::{DifferenceOrder, fornberg_weights, line_derivative};
+
+/// Interpolation between the points of a grid, applied along one grid
+/// direction at a time
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Linear between the two neighbouring points
+    Bilinear,
+    /// Cubic Hermite between the two neighbouring points, with the slopes
+    /// from fourth order differences
+    Bicubic,
+    /// Lagrange polynomial through the given number of points nearest to
+    /// the evaluation point
+    Lagrange(usize),
+}
+
+/// Interpolates the values `f(0)..=f(last)` of a grid line at `t`, given in
+/// units of the grid spacing. Values of `t` outside `0..=last` extrapolate.
+pub fn interpolate_line(f: &dyn Fn(usize) -> f32, last: usize, t: f32, method: Interpolation) -> f32 {
+    if last == 0 {
+        return f(0)
+    }
+    let k = (t.floor().max(0_f32) as usize).min(last - 1);
+    let s = t - (k as f32);
+    match method {
+        Interpolation::Bilinear => (1_f32 - s) * f(k) + s * f(k + 1),
+        Interpolation::Bicubic => {
+            let slope = |k: usize| line_derivative(f, k, last, 1_f32, DifferenceOrder::Fourth);
+            let (s2, s3) = (s * s, s * s * s);
+            (2_f32*s3 - 3_f32*s2 + 1_f32) * f(k)
+                + (s3 - 2_f32*s2 + s) * slope(k)
+                + (-2_f32*s3 + 3_f32*s2) * f(k + 1)
+                + (s3 - s2) * slope(k + 1)
+        },
+        Interpolation::Lagrange(points) => {
+            assert!(points >= 1, "Lagrange interpolation needs at least one point");
+            let points = points.min(last + 1);
+            // The points nearest to t, shifted inwards at the ends of the line
+            let start = ((t - ((points - 1) as f32) / 2_f32).round().max(0_f32) as usize).min(last + 1 - points);
+            let x: Vec<f64> = (0..points).map(|l| (start + l) as f64).collect();
+            fornberg_weights(t as f64, &x, 0).iter().enumerate()
+                .map(|(l, w)| (*w as f32) * f(start + l))
+                .sum()
+        },
+    }
+}
+
+/// Interpolates the values `f(i,j)` of an `n`×`m` grid at (`t_ξ`, `t_η`),
+/// given in units of the grid spacings, by interpolating along η on the
+/// lines of constant i that the interpolation along ξ needs
+pub fn interpolate_grid(
+    f: &dyn Fn(usize, usize) -> f32, n: usize, m: usize, t_ξ: f32, t_η: f32, method: Interpolation) -> f32
+{
+    let line = |i: usize| -> f32 { interpolate_line(&|j: usize| f(i, j), m - 1, t_η, method) };
+    interpolate_line(&line, n - 1, t_ξ, method)
+}
+
+#[test]
+fn test_interpolate_line() {
+    let cubic = |k: usize| -> f32 { let x = k as f32; x*x*x - 2_f32*x + 1_f32 };
+    let exact = |x: f32| -> f32 { x*x*x - 2_f32*x + 1_f32 };
+    for t in [0_f32, 0.3, 2.5, 7.9, 8_f32] {
+        // Fourth order slopes are exact for cubics, as are four Lagrange points
+        assert!((interpolate_line(&cubic, 8, t, Interpolation::Bicubic) - exact(t)).abs() < 1e-3 * (1_f32 + exact(t).abs()));
+        assert!((interpolate_line(&cubic, 8, t, Interpolation::Lagrange(4)) - exact(t)).abs() < 1e-3 * (1_f32 + exact(t).abs()));
+    }
+    assert_eq!(interpolate_line(&cubic, 8, 2.25, Interpolation::Bilinear), 0.75 * cubic(2) + 0.25 * cubic(3));
+    assert_eq!(interpolate_line(&cubic, 8, 5_f32, Interpolation::Lagrange(1)), cubic(5));
+}
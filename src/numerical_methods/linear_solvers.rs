@@ -0,0 +1,426 @@
+use crate::numerical_methods::sparse::CsrMatrix;
+
+use nalgebra::{DMatrix, DVector};
+
+/// Square linear maps x -> Ax that the Krylov solvers only need to apply
+pub trait LinearOperator {
+    fn size(&self) -> usize;
+    fn apply(&self, x: &DVector<f32>) -> DVector<f32>;
+}
+
+impl LinearOperator for CsrMatrix {
+    fn size(&self) -> usize {
+        self.get_nrows()
+    }
+    fn apply(&self, x: &DVector<f32>) -> DVector<f32> {
+        self.mul_vector(x)
+    }
+}
+
+impl LinearOperator for DMatrix<f32> {
+    fn size(&self) -> usize {
+        self.nrows()
+    }
+    fn apply(&self, x: &DVector<f32>) -> DVector<f32> {
+        self * x
+    }
+}
+
+/// Operators given by a closure applying them, for instance a discrete
+/// differential operator on `GridFunction` values, without assembling a matrix
+pub struct MatrixFree<F: Fn(&DVector<f32>) -> DVector<f32>> {
+    size: usize,
+    f: F,
+}
+
+impl<F: Fn(&DVector<f32>) -> DVector<f32>> MatrixFree<F> {
+    /// The operator `f` on vectors of length `size`
+    pub fn from(size: usize, f: F) -> MatrixFree<F> {
+        MatrixFree{size, f}
+    }
+}
+
+impl<F: Fn(&DVector<f32>) -> DVector<f32>> LinearOperator for MatrixFree<F> {
+    fn size(&self) -> usize {
+        self.size
+    }
+    fn apply(&self, x: &DVector<f32>) -> DVector<f32> {
+        (self.f)(x)
+    }
+}
+
+/// Approximations M of a matrix A, applied as z = M⁻¹r
+pub trait Preconditioner {
+    fn apply(&self, r: &DVector<f32>) -> DVector<f32>;
+}
+
+/// No preconditioning, M = I
+pub struct Identity;
+
+impl Preconditioner for Identity {
+    fn apply(&self, r: &DVector<f32>) -> DVector<f32> {
+        r.clone()
+    }
+}
+
+/// The diagonal of the matrix, M = D
+pub struct Jacobi {
+    inverse_diagonal: DVector<f32>,
+}
+
+impl Jacobi {
+    pub fn from(a: &CsrMatrix) -> Jacobi {
+        Jacobi{inverse_diagonal: a.diagonal().map(|d| 1_f32 / d)}
+    }
+}
+
+impl Preconditioner for Jacobi {
+    fn apply(&self, r: &DVector<f32>) -> DVector<f32> {
+        r.component_mul(&self.inverse_diagonal)
+    }
+}
+
+/// Symmetric successive over-relaxation with A = L + D + U and
+/// M = (D + ωL) D⁻¹ (D + ωU) / (ω(2-ω)), for `ω` in (0,2)
+pub struct Ssor {
+    a: CsrMatrix,
+    ω: f32,
+}
+
+impl Ssor {
+    pub fn from(a: &CsrMatrix, ω: f32) -> Ssor {
+        assert!(ω > 0_f32 && ω < 2_f32, "SSOR needs 0 < ω < 2");
+        Ssor{a: a.clone(), ω}
+    }
+}
+
+impl Preconditioner for Ssor {
+    fn apply(&self, r: &DVector<f32>) -> DVector<f32> {
+        let n = r.len();
+        let ω = self.ω;
+        // (D + ωL) y = r
+        let mut y = r.clone();
+        for i in 0..n {
+            let (columns, values) = self.a.row(i);
+            let mut sum = y[i];
+            for (j, v) in columns.iter().zip(values).filter(|(j, _)| **j < i) {
+                sum -= ω * v * y[*j];
+            }
+            y[i] = sum / self.a.get(i, i);
+        }
+        // (D + ωU) z = ω(2-ω) D y
+        let mut z = DVector::zeros(n);
+        for i in (0..n).rev() {
+            let (columns, values) = self.a.row(i);
+            let d = self.a.get(i, i);
+            let mut sum = ω * (2_f32 - ω) * d * y[i];
+            for (j, v) in columns.iter().zip(values).filter(|(j, _)| **j > i) {
+                sum -= ω * v * z[*j];
+            }
+            z[i] = sum / d;
+        }
+        z
+    }
+}
+
+/// Incomplete LU factorization with the sparsity pattern of the matrix,
+/// stored together with a unit diagonal L left out
+pub struct Ilu0 {
+    lu: CsrMatrix,
+}
+
+impl Ilu0 {
+    pub fn from(a: &CsrMatrix) -> Ilu0 {
+        let mut lu = a.clone();
+        for i in 1..lu.get_nrows() {
+            let columns: Vec<usize> = lu.row(i).0.to_vec();
+            for (position_k, k) in columns.iter().enumerate().filter(|(_, k)| **k < i) {
+                let ik = lu.position(i, *k).unwrap();
+                let factor = lu.get_values()[ik] / lu.get(*k, *k);
+                lu.get_values_mut()[ik] = factor;
+                for j in columns.iter().skip(position_k + 1) {
+                    if let Some(kj) = lu.position(*k, *j) {
+                        let ij = lu.position(i, *j).unwrap();
+                        let update = factor * lu.get_values()[kj];
+                        lu.get_values_mut()[ij] -= update;
+                    }
+                }
+            }
+        }
+        Ilu0{lu}
+    }
+}
+
+impl Preconditioner for Ilu0 {
+    fn apply(&self, r: &DVector<f32>) -> DVector<f32> {
+        let n = r.len();
+        let mut y = r.clone();
+        for i in 0..n {
+            let (columns, values) = self.lu.row(i);
+            for (j, v) in columns.iter().zip(values).filter(|(j, _)| **j < i) {
+                y[i] -= v * y[*j];
+            }
+        }
+        for i in (0..n).rev() {
+            let (columns, values) = self.lu.row(i);
+            for (j, v) in columns.iter().zip(values).filter(|(j, _)| **j > i) {
+                y[i] -= v * y[*j];
+            }
+            y[i] /= self.lu.get(i, i);
+        }
+        y
+    }
+}
+
+/// Outcome of an iterative solver, where `residuals` is the history of
+/// the residual norm ‖b - Ax‖ starting with the initial guess
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolverResult {
+    pub x: DVector<f32>,
+    pub iterations: usize,
+    pub residuals: Vec<f32>,
+    pub converged: bool,
+}
+
+/// Solves Ax=b for symmetric positive definite `a` with the preconditioned
+/// conjugate gradient method, from initial guess `x0` until the residual is
+/// below `tol` relative to b
+pub fn conjugate_gradient(
+    a: &dyn LinearOperator, b: &DVector<f32>, x0: DVector<f32>,
+    preconditioner: &dyn Preconditioner, tol: f32, max_iterations: usize) -> SolverResult
+{
+    let target = tol * b.norm();
+    let mut x = x0;
+    let mut r = b - a.apply(&x);
+    let mut residuals = vec![r.norm()];
+    let mut z = preconditioner.apply(&r);
+    let mut p = z.clone();
+    let mut rz = r.dot(&z);
+    for it in 1..=max_iterations {
+        if residuals[it - 1] <= target {
+            return SolverResult{x, iterations: it - 1, residuals, converged: true}
+        }
+        let ap = a.apply(&p);
+        let alpha = rz / p.dot(&ap);
+        x += &p * alpha;
+        r -= &ap * alpha;
+        residuals.push(r.norm());
+        z = preconditioner.apply(&r);
+        let rz_new = r.dot(&z);
+        p = &z + &p * (rz_new / rz);
+        rz = rz_new;
+    }
+    let converged = residuals[max_iterations] <= target;
+    SolverResult{x, iterations: max_iterations, residuals, converged}
+}
+
+/// Solves Ax=b with the right preconditioned stabilized biconjugate
+/// gradient method, from initial guess `x0` until the residual is below
+/// `tol` relative to b
+pub fn bicgstab(
+    a: &dyn LinearOperator, b: &DVector<f32>, x0: DVector<f32>,
+    preconditioner: &dyn Preconditioner, tol: f32, max_iterations: usize) -> SolverResult
+{
+    let target = tol * b.norm();
+    let n = a.size();
+    let mut x = x0;
+    let mut r = b - a.apply(&x);
+    let shadow = r.clone();
+    let mut residuals = vec![r.norm()];
+    let (mut rho, mut alpha, mut ω) = (1_f32, 1_f32, 1_f32);
+    let mut v = DVector::zeros(n);
+    let mut p = DVector::zeros(n);
+    for it in 1..=max_iterations {
+        if residuals[it - 1] <= target {
+            return SolverResult{x, iterations: it - 1, residuals, converged: true}
+        }
+        let rho_new = shadow.dot(&r);
+        if rho_new == 0_f32 || ω == 0_f32 {
+            return SolverResult{x, iterations: it - 1, residuals, converged: false}
+        }
+        p = &r + (&p - &v * ω) * ((rho_new / rho) * (alpha / ω));
+        let p_hat = preconditioner.apply(&p);
+        v = a.apply(&p_hat);
+        alpha = rho_new / shadow.dot(&v);
+        let s = &r - &v * alpha;
+        if s.norm() <= target {
+            x += &p_hat * alpha;
+            residuals.push(s.norm());
+            return SolverResult{x, iterations: it, residuals, converged: true}
+        }
+        let s_hat = preconditioner.apply(&s);
+        let t = a.apply(&s_hat);
+        ω = t.dot(&s) / t.dot(&t);
+        x += &p_hat * alpha + &s_hat * ω;
+        r = &s - &t * ω;
+        residuals.push(r.norm());
+        rho = rho_new;
+    }
+    let converged = residuals[max_iterations] <= target;
+    SolverResult{x, iterations: max_iterations, residuals, converged}
+}
+
+/// Solves Ax=b with the right preconditioned GMRES method restarted every
+/// `restart` iterations, from initial guess `x0` until the residual is
+/// below `tol` relative to b.
+///
+/// The residuals within a cycle are the ones GMRES minimizes, which are
+/// the true residuals up to rounding.
+pub fn gmres(
+    a: &dyn LinearOperator, b: &DVector<f32>, x0: DVector<f32>,
+    preconditioner: &dyn Preconditioner, restart: usize, tol: f32, max_iterations: usize) -> SolverResult
+{
+    let target = tol * b.norm();
+    let mut x = x0;
+    let mut r = b - a.apply(&x);
+    let mut residuals = vec![r.norm()];
+    let mut iterations = 0;
+    while iterations < max_iterations && residuals[residuals.len() - 1] > target {
+        let β = r.norm();
+        let mut basis = vec![&r / β];
+        let mut h = DMatrix::<f32>::zeros(restart + 1, restart);
+        let mut rotations: Vec<(f32, f32)> = Vec::with_capacity(restart);
+        let mut g = DVector::<f32>::zeros(restart + 1);
+        g[0] = β;
+        let mut k = 0;
+        while k < restart && iterations < max_iterations {
+            let mut w = a.apply(&preconditioner.apply(&basis[k]));
+            for (l, v) in basis.iter().enumerate() {
+                h[(l, k)] = w.dot(v);
+                w -= v * h[(l, k)];
+            }
+            h[(k + 1, k)] = w.norm();
+            for (l, (c, s)) in rotations.iter().enumerate() {
+                let (h0, h1) = (h[(l, k)], h[(l + 1, k)]);
+                h[(l, k)] = c * h0 + s * h1;
+                h[(l + 1, k)] = -s * h0 + c * h1;
+            }
+            let (h0, h1) = (h[(k, k)], h[(k + 1, k)]);
+            let norm = h0.hypot(h1);
+            let (c, s) = (h0 / norm, h1 / norm);
+            rotations.push((c, s));
+            h[(k, k)] = norm;
+            h[(k + 1, k)] = 0_f32;
+            g[k + 1] = -s * g[k];
+            g[k] *= c;
+
+            let w_norm = w.norm();
+            basis.push(w / w_norm);
+            k += 1;
+            iterations += 1;
+            residuals.push(g[k].abs());
+            if g[k].abs() <= target || w_norm == 0_f32 {
+                break;
+            }
+        }
+        // Back substitution for the least squares solution in the basis
+        let mut y = DVector::<f32>::zeros(k);
+        for i in (0..k).rev() {
+            let mut sum = g[i];
+            for j in i + 1..k {
+                sum -= h[(i, j)] * y[j];
+            }
+            y[i] = sum / h[(i, i)];
+        }
+        let mut update = DVector::zeros(x.len());
+        for (i, y_i) in y.iter().enumerate() {
+            update += &basis[i] * *y_i;
+        }
+        x += preconditioner.apply(&update);
+        r = b - a.apply(&x);
+    }
+    let converged = residuals[residuals.len() - 1] <= target;
+    SolverResult{x, iterations, residuals, converged}
+}
+
+/// The five point -Δu + c u_x with Dirichlet conditions on an N×N interior
+/// grid, with upwinded convection
+#[cfg(test)]
+fn convection_diffusion(size: usize, c: f32) -> CsrMatrix {
+    let h = 1_f32 / ((size + 1) as f32);
+    let index = |i: usize, j: usize| -> usize { i * size + j };
+    let mut triplets = Vec::new();
+    for i in 0..size {
+        for j in 0..size {
+            let k = index(i, j);
+            triplets.push((k, k, 4_f32 / (h * h) + c / h));
+            if i > 0 {
+                triplets.push((k, index(i - 1, j), -1_f32 / (h * h) - c / h));
+            }
+            if i + 1 < size {
+                triplets.push((k, index(i + 1, j), -1_f32 / (h * h)));
+            }
+            if j > 0 {
+                triplets.push((k, index(i, j - 1), -1_f32 / (h * h)));
+            }
+            if j + 1 < size {
+                triplets.push((k, index(i, j + 1), -1_f32 / (h * h)));
+            }
+        }
+    }
+    CsrMatrix::from_triplets(size * size, size * size, &triplets)
+}
+
+#[test]
+fn test_conjugate_gradient_preconditioners() {
+    let a = convection_diffusion(15, 0_f32);
+    let b = DVector::from_element(a.size(), 1_f32);
+    let x0 = DVector::zeros(a.size());
+    let tol = 1e-5;
+
+    let plain = conjugate_gradient(&a, &b, x0.clone(), &Identity, tol, 500);
+    let jacobi = conjugate_gradient(&a, &b, x0.clone(), &Jacobi::from(&a), tol, 500);
+    let ssor = conjugate_gradient(&a, &b, x0.clone(), &Ssor::from(&a, 1.5), tol, 500);
+    let ilu = conjugate_gradient(&a, &b, x0.clone(), &Ilu0::from(&a), tol, 500);
+    for result in [&plain, &jacobi, &ssor, &ilu] {
+        assert!(result.converged);
+        assert_eq!(result.residuals.len(), result.iterations + 1);
+        assert_eq!(result.residuals[0], b.norm());
+        assert!((&a.mul_vector(&result.x) - &b).norm() <= 2_f32 * tol * b.norm());
+    }
+    assert!(ssor.iterations < plain.iterations && ilu.iterations < plain.iterations);
+
+    // The same operator without the matrix
+    let operator = MatrixFree::from(a.size(), |x: &DVector<f32>| a.mul_vector(x));
+    let matrix_free = conjugate_gradient(&operator, &b, x0, &Identity, tol, 500);
+    assert_eq!(matrix_free.iterations, plain.iterations);
+    assert!((&matrix_free.x - &plain.x).norm() < 1e-6 * plain.x.norm());
+}
+
+#[test]
+fn test_nonsymmetric_solvers() {
+    let a = convection_diffusion(10, 50_f32);
+    let exact = DVector::from_fn(a.size(), |k, _| ((k as f32) / 7_f32).sin());
+    let b = a.mul_vector(&exact);
+    let x0 = DVector::zeros(a.size());
+    let tol = 1e-5;
+    let ilu = Ilu0::from(&a);
+
+    let results = [
+        bicgstab(&a, &b, x0.clone(), &Identity, tol, 500),
+        bicgstab(&a, &b, x0.clone(), &ilu, tol, 500),
+        gmres(&a, &b, x0.clone(), &Identity, 20, tol, 1000),
+        gmres(&a, &b, x0.clone(), &ilu, 20, tol, 1000),
+    ];
+    for result in results.iter() {
+        assert!(result.converged);
+        assert!((&result.x - &exact).norm() < 1e-2 * exact.norm());
+    }
+    assert!(results[1].iterations < results[0].iterations);
+    assert!(results[3].iterations < results[2].iterations);
+    // Within a cycle GMRES never increases the residual
+    assert!(results[3].residuals.windows(2).all(|r| r[1] <= r[0] * 1.0001));
+}
+
+#[test]
+fn test_ilu0_is_exact_for_tridiagonal() {
+    let a = CsrMatrix::from_dense(&DMatrix::from_row_slice(4, 4, &[
+        4_f32, -1_f32, 0_f32, 0_f32,
+        -2_f32, 4_f32, -1_f32, 0_f32,
+        0_f32, -2_f32, 4_f32, -1_f32,
+        0_f32, 0_f32, -2_f32, 4_f32,
+    ]));
+    let r = DVector::from_vec(vec![1_f32, 2_f32, 3_f32, 4_f32]);
+    let z = Ilu0::from(&a).apply(&r);
+    assert!((a.mul_vector(&z) - r).norm() < 1e-5);
+}
@@ -1,2 +1,12 @@
 pub mod newton;
+pub mod root_finding;
+pub mod quadrature;
+pub mod newton_system;
+pub mod ode;
+pub mod sparse;
+pub mod linear_solvers;
+pub mod convergence;
+pub mod finite_differences;
+pub mod interpolation;
+pub mod sbp;
 pub(crate) mod asymptotic_simpsons;
@@ -0,0 +1,198 @@
+use crate::duals::DualNumber;
+
+use nalgebra::{DMatrix, DVector};
+
+/// Vector valued functions of several variables evaluated on dual numbers,
+/// for finding their Jacobians by automatic differentiation
+pub type DualSystem<'a> = dyn Fn(&[DualNumber<f32>]) -> Vec<DualNumber<f32>> + 'a;
+
+type ValueAndJacobian<'a> = dyn Fn(&DVector<f32>) -> (DVector<f32>, DMatrix<f32>) + 'a;
+
+/// Outcome of Newton's method for a system, with the last iterate even
+/// when it didn't converge
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemResult {
+    pub x: DVector<f32>,
+    pub iterations: u32,
+    /// The euclidean norm of F at `x`
+    pub residual: f32,
+    pub converged: bool,
+}
+
+/// Solves the system `f=0` for x in R^n using Newton's method with initial
+/// guess `x0` and Jacobian `jacobian`.
+///
+/// Steps are shortened by backtracking until ‖f‖² decreases sufficiently
+/// along the step, and when the Jacobian is singular or so close to it that
+/// its pivots differ by more than the precision, the steepest descent
+/// direction of ‖f‖² is used instead.
+pub fn newton_system(
+    f: &dyn Fn(&DVector<f32>) -> DVector<f32>,
+    jacobian: &dyn Fn(&DVector<f32>) -> DMatrix<f32>,
+    x0: DVector<f32>) -> SystemResult
+{
+    let f_and_jacobian = |x: &DVector<f32>| -> (DVector<f32>, DMatrix<f32>) { (f(x), jacobian(x)) };
+    _newton_system(&f_and_jacobian, &|x: &DVector<f32>| f(x), x0, 1e-5, 50, true)
+}
+
+/// `newton_system` without reporting failures, for callers that report them
+/// in their own results or probe where failing is an expected outcome
+pub(crate) fn newton_system_quiet(
+    f: &dyn Fn(&DVector<f32>) -> DVector<f32>,
+    jacobian: &dyn Fn(&DVector<f32>) -> DMatrix<f32>,
+    x0: DVector<f32>) -> SystemResult
+{
+    let f_and_jacobian = |x: &DVector<f32>| -> (DVector<f32>, DMatrix<f32>) { (f(x), jacobian(x)) };
+    _newton_system(&f_and_jacobian, &|x: &DVector<f32>| f(x), x0, 1e-5, 50, false)
+}
+
+/// Solves the system `f=0` for x in R^n using Newton's method with initial
+/// guess `x0`, where the Jacobian is found by automatic differentiation.
+/// See `newton_system`.
+pub fn newton_system_dual(
+    f: &DualSystem<'_>, x0: DVector<f32>) -> SystemResult
+{
+    let f_and_jacobian = |x: &DVector<f32>| -> (DVector<f32>, DMatrix<f32>) { dual_jacobian(f, x) };
+    let f_real = |x: &DVector<f32>| -> DVector<f32> {
+        let x: Vec<DualNumber<f32>> = x.iter().map(|x| DualNumber::real(*x)).collect();
+        DVector::from_iterator(x.len(), f(&x).iter().map(|fx| fx.get_a()))
+    };
+    _newton_system(&f_and_jacobian, &f_real, x0, 1e-5, 50, true)
+}
+
+/// Value and Jacobian of `f` at `x`, one column per evaluation of `f`
+/// with the dual part of one component set
+pub fn dual_jacobian(
+    f: &DualSystem<'_>, x: &DVector<f32>) -> (DVector<f32>, DMatrix<f32>)
+{
+    let n = x.len();
+    let mut values = DVector::zeros(0);
+    let mut jacobian = DMatrix::zeros(0, n);
+    for k in 0..n {
+        let seeded: Vec<DualNumber<f32>> = x.iter().enumerate()
+            .map(|(l, x_l)| DualNumber::from(*x_l, if l == k {1_f32} else {0_f32}))
+            .collect();
+        let fx = f(&seeded);
+        if k == 0 {
+            values = DVector::from_iterator(fx.len(), fx.iter().map(|fx| fx.get_a()));
+            jacobian = DMatrix::zeros(fx.len(), n);
+        }
+        for (row, fx_row) in fx.iter().enumerate() {
+            jacobian[(row, k)] = fx_row.get_b();
+        }
+    }
+    (values, jacobian)
+}
+
+fn _newton_system(
+    f_and_jacobian: &ValueAndJacobian<'_>,
+    f: &dyn Fn(&DVector<f32>) -> DVector<f32>,
+    x0: DVector<f32>, tol: f32, max_it: u32, report: bool) -> SystemResult
+{
+    // Sufficient decrease factor and smallest step of the line search
+    let decrease = 1e-4_f32;
+    let λ_min = 1e-4_f32;
+
+    let mut x = x0;
+    let (mut fx, mut jacobian) = f_and_jacobian(&x);
+    let mut residual = fx.norm();
+    // Relative to the initial residual since f32 can't resolve much more
+    let tol_f = tol * residual.max(1_f32);
+    for it in 1..=max_it {
+        if residual <= tol_f {
+            return SystemResult{x, iterations: it - 1, residual, converged: true}
+        }
+        let lu = jacobian.clone().lu();
+        let pivots = lu.u().diagonal().abs();
+        let regular = pivots.min() > f32::EPSILON * pivots.max();
+        let gradient = jacobian.transpose() * &fx;
+        let direction = match lu.solve(&(-&fx)) {
+            Some(dx) if regular && dx.iter().all(|d| d.is_finite()) => dx,
+            _ => -&gradient,
+        };
+        // Directional derivative of ‖f‖²/2, which is -‖f‖² for Newton steps
+        let slope = gradient.dot(&direction);
+        let mut λ = 1_f32;
+        let (x_new, f_new) = loop {
+            let x_new = &x + &direction * λ;
+            let f_new = f(&x_new);
+            if f_new.norm_squared() <= residual * residual + 2_f32 * decrease * λ * slope {
+                break (x_new, f_new);
+            }
+            λ /= 2_f32;
+            if λ < λ_min {
+                if report {
+                    println!("Line search failed in newton's method for systems");
+                }
+                return SystemResult{x, iterations: it, residual, converged: false}
+            }
+        };
+        let step = (&x_new - &x).norm();
+        x = x_new;
+        residual = f_new.norm();
+        if step < f32::EPSILON * (1_f32 + x.norm()) {
+            if report {
+                println!("Newton's method for systems stagnated");
+            }
+            return SystemResult{x, iterations: it, residual, converged: residual <= tol_f}
+        }
+        (fx, jacobian) = f_and_jacobian(&x);
+    }
+    if report {
+        println!("No convergence in newton's method for systems");
+    }
+    SystemResult{x, iterations: max_it, residual, converged: residual <= tol_f}
+}
+
+#[test]
+fn test_newton_system() {
+    // Intersection of the circle x²+y²=4 with y=eˣ
+    let f = |x: &DVector<f32>| -> DVector<f32> {
+        DVector::from_vec(vec![x[0]*x[0] + x[1]*x[1] - 4_f32, x[1] - x[0].exp()])
+    };
+    let jacobian = |x: &DVector<f32>| -> DMatrix<f32> {
+        DMatrix::from_row_slice(2, 2, &[2_f32*x[0], 2_f32*x[1], -x[0].exp(), 1_f32])
+    };
+    let f_dual = |x: &[DualNumber<f32>]| -> Vec<DualNumber<f32>> {
+        vec![x[0]*x[0] + x[1]*x[1] - 4_f32, x[1] - x[0].exp()]
+    };
+    let x0 = DVector::from_vec(vec![1_f32, 1_f32]);
+    let exact = newton_system(&f, &jacobian, x0.clone());
+    let dual = newton_system_dual(&f_dual, x0.clone());
+    assert!(exact.converged && dual.converged);
+    assert!((&exact.x - &dual.x).norm() < 1e-5);
+    assert!(f(&exact.x).norm() < 1e-5);
+
+    let (value, dual_jacobian) = dual_jacobian(&f_dual, &x0);
+    assert!((value - f(&x0)).norm() < 1e-6);
+    assert!((dual_jacobian - jacobian(&x0)).norm() < 1e-6);
+}
+
+#[test]
+fn test_newton_system_line_search() {
+    // Full Newton steps overshoot for atan, the line search keeps it converging
+    let f = |x: &[DualNumber<f32>]| -> Vec<DualNumber<f32>> {
+        vec![x[0].atan(), (x[1] - x[0]) * 10_f32]
+    };
+    let result = newton_system_dual(&f, DVector::from_vec(vec![5_f32, -3_f32]));
+    assert!(result.converged);
+    assert!(result.x.norm() < 1e-3);
+
+    // No real solutions
+    let g = |x: &[DualNumber<f32>]| -> Vec<DualNumber<f32>> {
+        vec![x[0]*x[0] + 1_f32]
+    };
+    let result = newton_system_dual(&g, DVector::from_vec(vec![2_f32]));
+    assert!(!result.converged);
+
+    // The Jacobian is singular at the first guess and nearly singular at the
+    // second, where the Newton step would be huge, so both start downhill
+    let h = |x: &[DualNumber<f32>]| -> Vec<DualNumber<f32>> {
+        vec![x[0]*x[0] - 4_f32, x[1] - x[0]]
+    };
+    for x0 in [0_f32, 1e-20].iter() {
+        let result = newton_system_dual(&h, DVector::from_vec(vec![*x0, 1_f32]));
+        assert!(result.converged);
+        assert!((result.x[0].abs() - 2_f32).abs() < 1e-4 && (result.x[1] - result.x[0]).abs() < 1e-4);
+    }
+}
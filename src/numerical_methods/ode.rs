@@ -0,0 +1,196 @@
+use crate::numerical_methods::newton_system::newton_system_quiet;
+
+use nalgebra::{DMatrix, DVector};
+
+/// Right hand sides f(t,y) of the system y' = f(t,y)
+pub type OdeFunction<'a> = dyn Fn(f32, &DVector<f32>) -> DVector<f32> + 'a;
+
+/// Jacobians ∂f/∂y(t,y) of right hand sides
+pub type OdeJacobian<'a> = dyn Fn(f32, &DVector<f32>) -> DMatrix<f32> + 'a;
+
+/// The state `y` at time `t` that an integrator reached, with `steps`
+/// accepted and `rejected` steps
+#[derive(Debug, Clone, PartialEq)]
+pub struct OdeSolution {
+    pub t: f32,
+    pub y: DVector<f32>,
+    pub steps: usize,
+    pub rejected: usize,
+    /// `false` if the integrator had to stop before the final time
+    pub converged: bool,
+}
+
+/// Integrates y' = `f`(t,y) from `t0` to `t1` with initial value `y_initial`,
+/// using `steps` steps of the classical Runge–Kutta method. Without steps
+/// the solution stays at `t0`, which only reaches `t1` if they are equal.
+pub fn rk4(f: &OdeFunction<'_>, y_initial: DVector<f32>, t0: f32, t1: f32, steps: usize) -> OdeSolution {
+    if steps == 0 {
+        return OdeSolution{t: t0, y: y_initial, steps, rejected: 0, converged: t0 == t1}
+    }
+    let h = (t1 - t0) / (steps as f32);
+    let mut y = y_initial;
+    for k in 0..steps {
+        y = rk4_step(f, t0 + (k as f32) * h, &y, h);
+    }
+    OdeSolution{t: t1, y, steps, rejected: 0, converged: true}
+}
+
+/// One step of length `h` of the classical Runge–Kutta method from y(t)=`y`
+pub fn rk4_step(f: &OdeFunction<'_>, t: f32, y: &DVector<f32>, h: f32) -> DVector<f32> {
+    let k1 = f(t, y);
+    let k2 = f(t + h / 2_f32, &(y + &k1 * (h / 2_f32)));
+    let k3 = f(t + h / 2_f32, &(y + &k2 * (h / 2_f32)));
+    let k4 = f(t + h, &(y + &k3 * h));
+    y + (k1 + k2 * 2_f32 + k3 * 2_f32 + k4) * (h / 6_f32)
+}
+
+/// Nodes, coefficients and the 5th and 4th order weights of Dormand–Prince
+const DP_C: [f32; 7] = [0.0, 1.0/5.0, 3.0/10.0, 4.0/5.0, 8.0/9.0, 1.0, 1.0];
+const DP_A: [[f32; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0/5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0/40.0, 9.0/40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0/45.0, -56.0/15.0, 32.0/9.0, 0.0, 0.0, 0.0],
+    [19372.0/6561.0, -25360.0/2187.0, 64448.0/6561.0, -212.0/729.0, 0.0, 0.0],
+    [9017.0/3168.0, -355.0/33.0, 46732.0/5247.0, 49.0/176.0, -5103.0/18656.0, 0.0],
+    [35.0/384.0, 0.0, 500.0/1113.0, 125.0/192.0, -2187.0/6784.0, 11.0/84.0],
+];
+const DP_B5: [f32; 7] = [35.0/384.0, 0.0, 500.0/1113.0, 125.0/192.0, -2187.0/6784.0, 11.0/84.0, 0.0];
+const DP_B4: [f32; 7] = [
+    5179.0/57600.0, 0.0, 7571.0/16695.0, 393.0/640.0, -92097.0/339200.0, 187.0/2100.0, 1.0/40.0,
+];
+
+/// Integrates y' = `f`(t,y) from `t0` to `t1` with initial value `y_initial`, using
+/// the Dormand–Prince 4(5) pair with the step size controlled so that the
+/// local error is below `tol`, relative to the size of y when above 1.
+pub fn dormand_prince(f: &OdeFunction<'_>, y_initial: DVector<f32>, t0: f32, t1: f32, tol: f32) -> OdeSolution {
+    _dormand_prince(f, y_initial, t0, t1, tol, 100_000)
+}
+
+fn _dormand_prince(
+    f: &OdeFunction<'_>, y_initial: DVector<f32>, t0: f32, t1: f32, tol: f32, max_steps: usize) -> OdeSolution
+{
+    let mut t = t0;
+    let mut y = y_initial;
+    let mut h = (t1 - t0) / 100_f32;
+    let (mut steps, mut rejected) = (0, 0);
+    while (t1 - t) * (t1 - t0).signum() > 0_f32 {
+        if steps + rejected == max_steps {
+            return OdeSolution{t, y, steps, rejected, converged: false}
+        }
+        if (t + h - t1) * h.signum() > 0_f32 {
+            h = t1 - t;
+        }
+        let mut k: Vec<DVector<f32>> = Vec::with_capacity(7);
+        for s in 0..7 {
+            let mut y_s = y.clone();
+            for (l, k_l) in k.iter().enumerate() {
+                y_s += k_l * (h * DP_A[s][l]);
+            }
+            k.push(f(t + DP_C[s] * h, &y_s));
+        }
+        let mut y5 = y.clone();
+        let mut error = DVector::zeros(y.len());
+        for (s, k_s) in k.iter().enumerate() {
+            y5 += k_s * (h * DP_B5[s]);
+            error += k_s * (h * (DP_B5[s] - DP_B4[s]));
+        }
+        // Root mean square of the error scaled by the tolerance
+        let scaled = error.iter().zip(y.iter().zip(y5.iter()))
+            .map(|(e, (y_old, y_new))| e / (tol * (1_f32).max(y_old.abs()).max(y_new.abs())))
+            .map(|e| e * e)
+            .sum::<f32>() / (y.len() as f32);
+        let error_norm = scaled.sqrt();
+        if error_norm <= 1_f32 {
+            t += h;
+            y = y5;
+            steps += 1;
+        } else {
+            rejected += 1;
+        }
+        let factor = match error_norm == 0_f32 {
+            true => 5_f32,
+            false => (0.9 * error_norm.powf(-0.2)).clamp(0.2, 5_f32),
+        };
+        h *= factor;
+    }
+    OdeSolution{t: t1, y, steps, rejected, converged: true}
+}
+
+/// Integrates y' = `f`(t,y) from `t0` to `t1` with initial value `y_initial`, using
+/// `steps` steps of the implicit second order backward differentiation
+/// formula, started with a backward Euler step. `jacobian` is ∂f/∂y.
+///
+/// Each step solves a nonlinear system with Newton's method, so the method
+/// stays stable for stiff problems with large steps.
+pub fn bdf2(
+    f: &OdeFunction<'_>, jacobian: &OdeJacobian<'_>, y_initial: DVector<f32>, t0: f32, t1: f32, steps: usize) -> OdeSolution
+{
+    let h = (t1 - t0) / (steps as f32);
+    let identity = DMatrix::<f32>::identity(y_initial.len(), y_initial.len());
+    let mut y_before = y_initial.clone();
+    let mut y = y_initial;
+    for k in 0..steps {
+        let t = t0 + ((k + 1) as f32) * h;
+        // y_{k+1} - β f(t, y_{k+1}) = rhs, which is backward Euler for the first step
+        let (rhs, β) = match k {
+            0 => (y.clone(), h),
+            _ => ((&y * 4_f32 - &y_before) / 3_f32, 2_f32 * h / 3_f32),
+        };
+        let g = |v: &DVector<f32>| -> DVector<f32> { v - f(t, v) * β - &rhs };
+        let dg = |v: &DVector<f32>| -> DMatrix<f32> { &identity - jacobian(t, v) * β };
+        let result = newton_system_quiet(&g, &dg, y.clone());
+        if !result.converged {
+            return OdeSolution{t: t - h, y, steps: k, rejected: 0, converged: false}
+        }
+        y_before = y;
+        y = result.x;
+    }
+    OdeSolution{t: t1, y, steps, rejected: 0, converged: true}
+}
+
+#[test]
+fn test_explicit_orders() {
+    // y' = -y + sin(t) with y(0)=1 has y = 1.5e^-t + (sin t - cos t)/2
+    let f = |t: f32, y: &DVector<f32>| -> DVector<f32> { y.map(|y| -y + t.sin()) };
+    let exact = 1.5 * (-2_f32).exp() + (2_f32.sin() - 2_f32.cos()) / 2_f32;
+    let y_initial = DVector::from_vec(vec![1_f32]);
+
+    let coarse = (rk4(&f, y_initial.clone(), 0_f32, 2_f32, 10).y[0] - exact).abs();
+    let fine = (rk4(&f, y_initial.clone(), 0_f32, 2_f32, 20).y[0] - exact).abs();
+    // Fourth order, with some room for f32 rounding
+    assert!(coarse / fine > 12_f32);
+    let none = rk4(&f, y_initial.clone(), 0_f32, 2_f32, 0);
+    assert!(!none.converged && none.t == 0_f32 && none.y == y_initial);
+
+    let result = dormand_prince(&f, y_initial, 0_f32, 2_f32, 1e-5);
+    assert!(result.converged && result.t == 2_f32);
+    assert!((result.y[0] - exact).abs() < 1e-4);
+    assert!(result.steps < 50);
+}
+
+#[test]
+fn test_bdf2_stiff() {
+    use crate::functions::GridFunction;
+    use crate::geometry::domain::Domain;
+    use std::sync::Arc;
+
+    let domain = Arc::new(Domain::unit_square(4, 4));
+    let u0 = GridFunction::from_fnc(&domain, &|x: f32, y: f32| x + y);
+
+    // Every value relaxes quickly to cos(t), much too stiff for explicit steps of 0.05
+    let λ = 1000_f32;
+    let f = |t: f32, y: &DVector<f32>| -> DVector<f32> { y.map(|y| -λ * (y - t.cos())) };
+    let jacobian = |_: f32, y: &DVector<f32>| -> DMatrix<f32> { DMatrix::identity(y.len(), y.len()) * -λ };
+    let result = bdf2(&f, &jacobian, u0.to_vector(), 0_f32, 1_f32, 20);
+    assert!(result.converged);
+    let u1 = GridFunction::from_vector(&domain, result.y);
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!((u1.get_value(i, j) - 1_f32.cos()).abs() < 1e-3);
+        }
+    }
+    // Blows up, or turns into NaN
+    let explicit = rk4(&f, u0.to_vector(), 0_f32, 1_f32, 20);
+    assert!(!explicit.y.iter().all(|y| y.abs() < 10_f32));
+}
@@ -0,0 +1,199 @@
+use crate::numerical_methods::asymptotic_simpsons::asi_result;
+
+/// Rules for integrating a closure over an interval
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Quadrature {
+    /// Adaptive Simpson, see `asymptotic_simpsons`
+    Simpson,
+    /// Adaptive 15-point Gauss–Kronrod with the embedded 7-point Gauss rule
+    GaussKronrod,
+    /// Fixed Gauss–Legendre with the given number of points
+    GaussLegendre(usize),
+}
+
+/// Outcome of a quadrature, where `error` estimates the absolute error
+/// of `value` and `evaluations` counts the calls to the integrand
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct QuadratureResult {
+    pub value: f32,
+    pub error: f32,
+    pub evaluations: usize,
+    /// `false` if the subdivision limit was hit before reaching the tolerance
+    pub converged: bool,
+}
+
+/// Absolute tolerance, relative to the size of the integral when it's above 1
+const TOL: f32 = 1e-6;
+const MAX_SUBDIVISIONS: usize = 100;
+
+/// Calculates the integral of closure `f` from `a` to `b` using `rule`.
+pub fn integrate(f: &dyn Fn(f32) -> f32, a: f32, b: f32, rule: Quadrature) -> QuadratureResult {
+    match rule {
+        Quadrature::Simpson => asi_result(f, a, b),
+        Quadrature::GaussKronrod => gauss_kronrod(f, a, b),
+        Quadrature::GaussLegendre(n) => gauss_legendre(f, a, b, n),
+    }
+}
+
+/// Nodes in [0,1] of the 15-point Kronrod rule, mirrored about 0
+const XGK: [f64; 8] = [
+    0.991_455_371_120_812_6, 0.949_107_912_342_758_5, 0.864_864_423_359_769_1, 0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1, 0.405_845_151_377_397_2, 0.207_784_955_007_898_5, 0.0,
+];
+/// Weights of the 15-point Kronrod rule
+const WGK: [f64; 8] = [
+    0.022_935_322_010_529_22, 0.063_092_092_629_978_55, 0.104_790_010_322_250_2, 0.140_653_259_715_525_9,
+    0.169_004_726_639_267_9, 0.190_350_578_064_785_4, 0.204_432_940_075_298_9, 0.209_482_141_084_727_8,
+];
+/// Weights of the 7-point Gauss rule at the odd Kronrod nodes
+const WG: [f64; 4] = [
+    0.129_484_966_168_869_7, 0.279_705_391_489_276_7, 0.381_830_050_505_118_9, 0.417_959_183_673_469_4,
+];
+
+/// Kronrod and Gauss approximations of the integral over [a,b]
+fn g7k15(f: &dyn Fn(f32) -> f32, a: f32, b: f32) -> (f32, f32) {
+    let c = 0.5 * (a as f64 + b as f64);
+    let h = 0.5 * (b as f64 - a as f64);
+    let fc = f(c as f32) as f64;
+    let mut kronrod = WGK[7] * fc;
+    let mut gauss = WG[3] * fc;
+    for k in 0..7 {
+        let pair = f((c - h * XGK[k]) as f32) as f64 + f((c + h * XGK[k]) as f32) as f64;
+        kronrod += WGK[k] * pair;
+        if k % 2 == 1 {
+            gauss += WG[k / 2] * pair;
+        }
+    }
+    ((kronrod * h) as f32, (gauss * h) as f32)
+}
+
+/// Calculates the integral of closure `f` from `a` to `b` by adaptive
+/// Gauss–Kronrod, bisecting the subinterval with the largest error.
+pub fn gauss_kronrod(f: &dyn Fn(f32) -> f32, a: f32, b: f32) -> QuadratureResult {
+    _gauss_kronrod(f, a, b, TOL, MAX_SUBDIVISIONS)
+}
+
+fn _gauss_kronrod(f: &dyn Fn(f32) -> f32, a: f32, b: f32, tol: f32, max_subdivisions: usize) -> QuadratureResult {
+    let (kronrod, gauss) = g7k15(f, a, b);
+    // Subintervals as (a, b, value, error)
+    let mut intervals = vec![(a, b, kronrod, (kronrod - gauss).abs())];
+    let mut evaluations = 15;
+    for _ in 0..max_subdivisions {
+        let value: f32 = intervals.iter().map(|i| i.2).sum();
+        let error: f32 = intervals.iter().map(|i| i.3).sum();
+        if error <= tol * value.abs().max(1_f32) {
+            return QuadratureResult{value, error, evaluations, converged: true}
+        }
+        let worst = (0..intervals.len())
+            .max_by(|&k, &l| intervals[k].3.total_cmp(&intervals[l].3))
+            .unwrap();
+        let (a, b, _, _) = intervals.swap_remove(worst);
+        let c = (a + b) / 2_f32;
+        for (a, b) in [(a, c), (c, b)] {
+            let (kronrod, gauss) = g7k15(f, a, b);
+            intervals.push((a, b, kronrod, (kronrod - gauss).abs()));
+        }
+        evaluations += 30;
+    }
+    let value: f32 = intervals.iter().map(|i| i.2).sum();
+    let error: f32 = intervals.iter().map(|i| i.3).sum();
+    QuadratureResult{value, error, evaluations, converged: error <= tol * value.abs().max(1_f32)}
+}
+
+/// Nodes and weights of the `n`-point Gauss–Legendre rule on [-1,1],
+/// with the nodes found by Newton's method on the Legendre polynomial.
+/// The rule of 0 points is empty.
+pub fn gauss_legendre_rule(n: usize) -> Vec<(f64, f64)> {
+    let mut rule = vec![(0_f64, 0_f64); n];
+    for k in 0..n.div_ceil(2) {
+        // Chebyshev-like guess for the k-th largest root
+        let mut x = (std::f64::consts::PI * (k as f64 + 0.75) / (n as f64 + 0.5)).cos();
+        for _ in 0..100 {
+            let (p, dp) = legendre(n, x);
+            let dx = p / dp;
+            x -= dx;
+            if dx.abs() < 1e-15 {
+                break;
+            }
+        }
+        let (_, dp) = legendre(n, x);
+        let w = 2_f64 / ((1_f64 - x * x) * dp * dp);
+        rule[k] = (-x, w);
+        rule[n - 1 - k] = (x, w);
+    }
+    rule
+}
+
+/// The Legendre polynomial P_n and its derivative at `x`
+fn legendre(n: usize, x: f64) -> (f64, f64) {
+    if n == 0 {
+        return (1_f64, 0_f64)
+    }
+    let (mut p0, mut p1) = (1_f64, x);
+    for k in 2..=n {
+        let p2 = ((2 * k - 1) as f64 * x * p1 - (k - 1) as f64 * p0) / k as f64;
+        p0 = p1;
+        p1 = p2;
+    }
+    (p1, n as f64 * (x * p1 - p0) / (x * x - 1_f64))
+}
+
+fn gauss_legendre_sum(f: &dyn Fn(f32) -> f32, a: f32, b: f32, rule: &[(f64, f64)]) -> f64 {
+    let c = 0.5 * (a as f64 + b as f64);
+    let h = 0.5 * (b as f64 - a as f64);
+    h * rule.iter().map(|(x, w)| w * f((c + h * x) as f32) as f64).sum::<f64>()
+}
+
+/// Calculates the integral of closure `f` from `a` to `b` with the
+/// `n`-point Gauss–Legendre rule. The error is estimated by comparing
+/// with the same rule on the two halves of the interval. Without points
+/// the result isn't converged.
+pub fn gauss_legendre(f: &dyn Fn(f32) -> f32, a: f32, b: f32, n: usize) -> QuadratureResult {
+    if n == 0 {
+        return QuadratureResult{value: 0_f32, error: f32::INFINITY, evaluations: 0, converged: false}
+    }
+    let rule = gauss_legendre_rule(n);
+    let c = (a + b) / 2_f32;
+    let whole = gauss_legendre_sum(f, a, b, &rule);
+    let halves = gauss_legendre_sum(f, a, c, &rule) + gauss_legendre_sum(f, c, b, &rule);
+    let value = halves as f32;
+    let error = (whole - halves).abs() as f32;
+    QuadratureResult{value, error, evaluations: 3 * n, converged: error <= TOL * value.abs().max(1_f32)}
+}
+
+#[test]
+fn test_gauss_legendre_rule() {
+    // Exact for polynomials of degree 2n-1
+    for n in 1..=12 {
+        let rule = gauss_legendre_rule(n);
+        assert!((rule.iter().map(|(_, w)| w).sum::<f64>() - 2_f64).abs() < 1e-12);
+        let degree = 2 * n - 1;
+        let x_deg: f64 = rule.iter().map(|(x, w)| w * x.powi(degree as i32 - 1)).sum();
+        assert!((x_deg - 2_f64 / degree as f64).abs() < 1e-12);
+    }
+    let rule = gauss_legendre_rule(2);
+    assert!((rule[1].0 - 1_f64 / 3_f64.sqrt()).abs() < 1e-15);
+}
+
+#[test]
+fn test_quadrature_rules() {
+    let f = |x: f32| -> f32 {x.exp() * x.cos()};
+    let exact = 0.5 * (std::f32::consts::PI.exp() * -1_f32 - 1_f32);
+    for rule in [Quadrature::Simpson, Quadrature::GaussKronrod, Quadrature::GaussLegendre(10)] {
+        let result = integrate(&f, 0_f32, std::f32::consts::PI, rule);
+        assert!(result.converged);
+        assert!((result.value - exact).abs() < 1e-4 * exact.abs());
+    }
+    assert!(!integrate(&f, 0_f32, std::f32::consts::PI, Quadrature::GaussLegendre(0)).converged);
+
+    // Singular derivative at 0, needs adaptivity
+    let sqrt = |x: f32| -> f32 {x.sqrt()};
+    let result = gauss_kronrod(&sqrt, 0_f32, 1_f32);
+    assert!(result.converged && (result.value - 2_f32 / 3_f32).abs() < 1e-5);
+    assert!(result.evaluations < 1000);
+
+    // Can't be resolved, the subdivision limit stops it
+    let wild = |x: f32| -> f32 {(1_f32 / x).sin()};
+    let result = _gauss_kronrod(&wild, 1e-6, 1_f32, 1e-7, 10);
+    assert!(!result.converged && result.evaluations == 15 + 10 * 30);
+}
@@ -0,0 +1,108 @@
+/// Outcome of a root finder, with the last iterate even when it
+/// didn't converge
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RootResult {
+    pub root: f32,
+    pub iterations: u32,
+    /// The value of `f` at `root`
+    pub residual: f32,
+    pub converged: bool,
+}
+
+/// Solves the equation `f=0` for `x` in the bracket `[a,b]` using Newton's
+/// method with initial guess `x0`, safeguarded by bisection. Requires
+/// derivative of `f` `df`, and that `f(a)` and `f(b)` have different signs.
+///
+/// A bisection step is taken whenever the Newton step would leave the
+/// current bracket, `df` is zero, or `f` doesn't decrease fast enough, so
+/// the iteration always converges to a root inside the bracket.
+pub fn newton_bracketed(
+    f: &dyn Fn(f32) -> f32, df: &dyn Fn(f32) -> f32, a: f32, b: f32, x0: f32) -> RootResult
+{
+    _newton_bracketed(f, df, a, b, x0, 1e-6, 100)
+}
+
+fn _newton_bracketed(
+    f: &dyn Fn(f32) -> f32, df: &dyn Fn(f32) -> f32, a: f32, b: f32, x0: f32, tol: f32, max_it: u32) -> RootResult
+{
+    let (fa, fb) = (f(a), f(b));
+    if fa == 0_f32 {
+        return RootResult{root: a, iterations: 0, residual: fa, converged: true}
+    }
+    if fb == 0_f32 {
+        return RootResult{root: b, iterations: 0, residual: fb, converged: true}
+    }
+    if fa.signum() == fb.signum() {
+        let (root, residual) = match fa.abs() < fb.abs() {
+            true => (a, fa),
+            false => (b, fb),
+        };
+        return RootResult{root, iterations: 0, residual, converged: false}
+    }
+    // Keep f(lo) < 0 < f(hi)
+    let (mut lo, mut hi) = match fa < 0_f32 {
+        true => (a, b),
+        false => (b, a),
+    };
+    let mut x = match x0 > a.min(b) && x0 < a.max(b) {
+        true => x0,
+        false => (a + b) / 2_f32,
+    };
+    let mut step_before = (hi - lo).abs();
+    let mut step = step_before;
+    let mut fx = f(x);
+    if fx == 0_f32 {
+        return RootResult{root: x, iterations: 0, residual: fx, converged: true}
+    }
+    for it in 1..=max_it {
+        if fx < 0_f32 {
+            lo = x;
+        } else {
+            hi = x;
+        }
+        let dfx = df(x);
+        let newton = x - fx / dfx;
+        let inside = newton.is_finite() && (newton - lo) * (newton - hi) < 0_f32;
+        let fast_enough = (2_f32 * fx).abs() <= (step_before * dfx).abs();
+        step_before = step;
+        let x_new = match inside && fast_enough {
+            true => newton,
+            false => (lo + hi) / 2_f32,
+        };
+        step = (x_new - x).abs();
+        x = x_new;
+        fx = f(x);
+        if fx == 0_f32 || step < tol * (1_f32 + x.abs()) || (hi - lo).abs() < tol {
+            return RootResult{root: x, iterations: it, residual: fx, converged: true}
+        }
+    }
+    RootResult{root: x, iterations: max_it, residual: fx, converged: false}
+}
+
+#[test]
+fn test_newton_bracketed() {
+    let delta = 1e-6;
+
+    let dottie = 0.73908514;
+    let f1 = |x: f32| -> f32 {x - x.cos()};
+    let df1 = |x: f32| -> f32 {1_f32 + x.sin()};
+    let result = newton_bracketed(&f1, &df1, 0_f32, 2_f32, 0.5_f32);
+    assert!(result.converged);
+    assert!((result.root - dottie).abs() < delta);
+    assert!(result.residual.abs() < delta);
+
+    // Newton from 0 has a zero derivative
+    let f2 = |x: f32| -> f32 {x*x*x - 8_f32};
+    let df2 = |x: f32| -> f32 {3_f32*x*x};
+    let result = newton_bracketed(&f2, &df2, 0_f32, 5_f32, 0_f32);
+    assert!(result.converged && (result.root - 2_f32).abs() < delta);
+
+    // Plain Newton diverges for atan from far away
+    let f3 = |x: f32| -> f32 {x.atan()};
+    let df3 = |x: f32| -> f32 {1_f32 / (1_f32 + x*x)};
+    let result = newton_bracketed(&f3, &df3, -3_f32, 10_f32, 9_f32);
+    assert!(result.converged && result.root.abs() < delta);
+
+    let result = newton_bracketed(&f3, &df3, 1_f32, 10_f32, 2_f32);
+    assert!(!result.converged);
+}
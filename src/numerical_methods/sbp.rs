@@ -0,0 +1,214 @@
+use crate::numerical_methods::finite_differences::DifferenceOrder;
+use crate::numerical_methods::sparse::CsrMatrix;
+
+use nalgebra::{DMatrix, DVector};
+
+use std::fmt::Display;
+
+/// Error from a line with fewer points than the boundary closures of an
+/// operator need
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TooFewPoints {
+    pub points: usize,
+    pub needed: usize,
+}
+
+impl Display for TooFewPoints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} points are too few for the SBP operator, which needs {}", self.points, self.needed)
+    }
+}
+
+impl std::error::Error for TooFewPoints {}
+
+/// Weights of the diagonal norm at the start of a line, in units of the
+/// grid spacing, for interior orders 2, 4 and 6
+fn boundary_norm(order: DifferenceOrder) -> Vec<f64> {
+    match order {
+        DifferenceOrder::Second => vec![1.0 / 2.0],
+        DifferenceOrder::Fourth => vec![17.0 / 48.0, 59.0 / 48.0, 43.0 / 48.0, 49.0 / 48.0],
+        DifferenceOrder::Sixth => vec![
+            13649.0 / 43200.0, 12013.0 / 8640.0, 2711.0 / 4320.0, 5359.0 / 4320.0, 7877.0 / 8640.0, 43801.0 / 43200.0,
+        ],
+    }
+}
+
+/// Coefficients c_1..c_p of the central difference sum c_k (f(x+k) - f(x-k))
+fn central_coefficients(order: DifferenceOrder) -> Vec<f64> {
+    match order {
+        DifferenceOrder::Second => vec![1.0 / 2.0],
+        DifferenceOrder::Fourth => vec![2.0 / 3.0, -1.0 / 12.0],
+        DifferenceOrder::Sixth => vec![3.0 / 4.0, -3.0 / 20.0, 1.0 / 60.0],
+    }
+}
+
+/// Summation-by-parts first derivative D = H⁻¹Q on a line of equidistant
+/// points, with the diagonal norm H and Q + Qᵀ = diag(-1,0,...,0,1).
+///
+/// The interior stencil is central of order 2p, and the closures at the
+/// ends are of order p, so the operators are globally of order p+1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbpOperator {
+    order: DifferenceOrder,
+    derivative: CsrMatrix,
+    norm: DVector<f32>,
+}
+
+impl SbpOperator {
+    /// The operator of interior order `order` for `points` points with
+    /// spacing `h`, which needs at least 3, 10 or 15 points for orders
+    /// 2, 4 and 6, or `TooFewPoints` otherwise.
+    ///
+    /// The boundary closure is the least squares solution of the accuracy
+    /// conditions for the known norm, which leaves one free parameter of
+    /// the sixth order closure at its minimum norm choice.
+    pub fn from(order: DifferenceOrder, points: usize, h: f32) -> Result<SbpOperator, TooFewPoints> {
+        let weights = boundary_norm(order);
+        let c = central_coefficients(order);
+        let (r, p) = (weights.len(), c.len());
+        if points < 2 * r + p {
+            return Err(TooFewPoints{points, needed: 2 * r + p})
+        }
+        let closure = boundary_closure(&weights, &c);
+
+        let last = points - 1;
+        let mut triplets = Vec::new();
+        for (a, weight) in weights.iter().enumerate() {
+            for (b, q) in closure.row(a).iter().enumerate() {
+                if *q != 0_f64 {
+                    let d = (q / weight) as f32 / h;
+                    triplets.push((a, b, d));
+                    // Mirrored at the end of the line
+                    triplets.push((last - a, last - b, -d));
+                }
+            }
+        }
+        for i in r..=last - r {
+            for (k, c_k) in c.iter().enumerate() {
+                triplets.push((i, i + k + 1, *c_k as f32 / h));
+                triplets.push((i, i - k - 1, -*c_k as f32 / h));
+            }
+        }
+        let norm = DVector::from_fn(points, |i, _| {
+            let k = i.min(last - i);
+            h * match k < r {
+                true => weights[k] as f32,
+                false => 1_f32,
+            }
+        });
+        Ok(SbpOperator{order, derivative: CsrMatrix::from_triplets(points, points, &triplets), norm})
+    }
+
+    pub fn get_order(&self) -> DifferenceOrder {
+        self.order
+    }
+
+    /// The derivative matrix D
+    pub fn get_derivative(&self) -> &CsrMatrix {
+        &self.derivative
+    }
+
+    /// The diagonal of the norm matrix H, which is also a quadrature rule
+    pub fn get_norm(&self) -> &DVector<f32> {
+        &self.norm
+    }
+
+    /// The norm matrix H
+    pub fn norm_matrix(&self) -> CsrMatrix {
+        let triplets: Vec<(usize, usize, f32)> = self.norm.iter().enumerate().map(|(i, w)| (i, i, *w)).collect();
+        CsrMatrix::from_triplets(self.norm.len(), self.norm.len(), &triplets)
+    }
+
+    /// Row `k` of D applied to the values `f(0)..` of the line
+    pub fn apply(&self, f: &dyn Fn(usize) -> f32, k: usize) -> f32 {
+        let (columns, values) = self.derivative.row(k);
+        columns.iter().zip(values).map(|(l, d)| d * f(*l)).sum()
+    }
+}
+
+/// The rows of Q at the start of a line with unit spacing, for the norm
+/// weights `weights` and the central coefficients `c`.
+///
+/// Q = S + B/2 where S is skew-symmetric, and its entries coupling the
+/// closure to the interior follow from the interior stencil. The remaining
+/// entries of S solve the conditions that D is exact for polynomials up to
+/// degree p at the closure.
+fn boundary_closure(weights: &[f64], c: &[f64]) -> DMatrix<f64> {
+    let (r, p) = (weights.len(), c.len());
+    let columns = r + p;
+    // Known part, with the unknowns S[a][b] for a<b<r set to zero
+    let mut q = DMatrix::<f64>::zeros(r, columns);
+    q[(0, 0)] = -0.5;
+    for a in 0..r {
+        for b in r.max(a + 1)..columns {
+            if b - a <= p {
+                q[(a, b)] = c[b - a - 1];
+            }
+        }
+    }
+    let unknowns: Vec<(usize, usize)> = (0..r).flat_map(|a| (a + 1..r).map(move |b| (a, b))).collect();
+    if unknowns.is_empty() {
+        return q
+    }
+    // One equation for each row and each degree
+    let power = |x: usize, q: usize| -> f64 { match q { 0 => 1_f64, _ => (x as f64).powi(q as i32) } };
+    let mut system = DMatrix::<f64>::zeros(r * (p + 1), unknowns.len());
+    let mut rhs = DVector::<f64>::zeros(r * (p + 1));
+    for a in 0..r {
+        for degree in 0..=p {
+            let row = a * (p + 1) + degree;
+            let exact = match degree {
+                0 => 0_f64,
+                _ => (degree as f64) * power(a, degree - 1),
+            };
+            let known: f64 = (0..columns).map(|b| q[(a, b)] * power(b, degree)).sum();
+            rhs[row] = weights[a] * exact - known;
+            for (u, (k, l)) in unknowns.iter().enumerate() {
+                // S[k][l] enters row k with x_l^q and row l with -x_k^q
+                if *k == a {
+                    system[(row, u)] += power(*l, degree);
+                }
+                if *l == a {
+                    system[(row, u)] -= power(*k, degree);
+                }
+            }
+        }
+    }
+    let s = system.svd(true, true).solve(&rhs, 1e-12).expect("SVD of the accuracy conditions failed");
+    for (u, (k, l)) in unknowns.iter().enumerate() {
+        q[(*k, *l)] = s[u];
+        q[(*l, *k)] = -s[u];
+    }
+    q
+}
+
+#[test]
+fn test_sbp_property_and_accuracy() {
+    let h = 0.05_f32;
+    for order in DifferenceOrder::ALL {
+        let points = 21;
+        let sbp = SbpOperator::from(order, points, h).unwrap();
+        // HD + (HD)ᵀ = diag(-1,0,...,0,1)
+        let q = sbp.norm_matrix().to_dense() * sbp.get_derivative().to_dense();
+        let mut b = DMatrix::<f32>::zeros(points, points);
+        b[(0, 0)] = -1_f32;
+        b[(points - 1, points - 1)] = 1_f32;
+        assert!((&q + q.transpose() - b).abs().max() < 1e-4);
+        assert!((sbp.get_norm().sum() - 1_f32).abs() < 1e-5);
+
+        // Exact up to degree p at the boundary and 2p inside
+        let p = order.get_order() / 2;
+        let f = |k: usize| -> f32 { ((k as f32) * h).powi(p as i32) };
+        for k in 0..points {
+            let x = (k as f32) * h;
+            assert!((sbp.apply(&f, k) - (p as f32) * x.powi(p as i32 - 1)).abs() < 1e-3);
+        }
+    }
+    // The known fourth order closure
+    let d = SbpOperator::from(DifferenceOrder::Fourth, 12, 1_f32).unwrap().get_derivative().to_dense();
+    let first_row = [-24_f32 / 17_f32, 59_f32 / 34_f32, -4_f32 / 17_f32, -3_f32 / 34_f32, 0_f32, 0_f32];
+    for (b, expected) in first_row.iter().enumerate() {
+        assert!((d[(0, b)] - expected).abs() < 1e-5);
+    }
+    assert_eq!(SbpOperator::from(DifferenceOrder::Sixth, 14, 1_f32), Err(TooFewPoints{points: 14, needed: 15}));
+}
@@ -0,0 +1,186 @@
+use nalgebra::{DMatrix, DVector};
+
+use std::fs::File;
+use std::io::Write;
+
+/// Sparse matrices in compressed sparse row format, where the entries of
+/// row i are at positions `row_offsets[i]..row_offsets[i+1]` of `columns`
+/// and `values`, sorted by column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrMatrix {
+    nrows: usize,
+    ncols: usize,
+    row_offsets: Vec<usize>,
+    columns: Vec<usize>,
+    values: Vec<f32>,
+}
+
+impl CsrMatrix {
+    /// Creates a `nrows`×`ncols` matrix from (row, column, value) triplets,
+    /// where values for the same position are added together
+    pub fn from_triplets(nrows: usize, ncols: usize, triplets: &[(usize, usize, f32)]) -> CsrMatrix {
+        let mut sorted = triplets.to_vec();
+        sorted.sort_by_key(|&(i, j, _)| (i, j));
+        let mut row_offsets = vec![0; nrows + 1];
+        let mut columns: Vec<usize> = Vec::with_capacity(sorted.len());
+        let mut values: Vec<f32> = Vec::with_capacity(sorted.len());
+        let mut last: Option<(usize, usize)> = None;
+        for (i, j, v) in sorted {
+            assert!(i < nrows && j < ncols, "Entry ({},{}) is outside the {}x{} matrix", i, j, nrows, ncols);
+            if last == Some((i, j)) {
+                *values.last_mut().unwrap() += v;
+            } else {
+                columns.push(j);
+                values.push(v);
+                row_offsets[i + 1] += 1;
+                last = Some((i, j));
+            }
+        }
+        for i in 0..nrows {
+            row_offsets[i + 1] += row_offsets[i];
+        }
+        CsrMatrix{nrows, ncols, row_offsets, columns, values}
+    }
+
+    /// Creates a sparse matrix from the nonzero entries of `matrix`
+    pub fn from_dense(matrix: &DMatrix<f32>) -> CsrMatrix {
+        let mut triplets = Vec::new();
+        for i in 0..matrix.nrows() {
+            for j in 0..matrix.ncols() {
+                if matrix[(i, j)] != 0_f32 {
+                    triplets.push((i, j, matrix[(i, j)]));
+                }
+            }
+        }
+        CsrMatrix::from_triplets(matrix.nrows(), matrix.ncols(), &triplets)
+    }
+
+    pub fn get_nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn get_ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Number of stored entries
+    pub fn get_nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The column indices and values of the stored entries in row `i`
+    pub fn row(&self, i: usize) -> (&[usize], &[f32]) {
+        let range = self.row_offsets[i]..self.row_offsets[i + 1];
+        (&self.columns[range.clone()], &self.values[range])
+    }
+
+    /// Position of entry (i,j) in `values`, if it's stored
+    pub(crate) fn position(&self, i: usize, j: usize) -> Option<usize> {
+        let (columns, _) = self.row(i);
+        columns.binary_search(&j).ok().map(|k| self.row_offsets[i] + k)
+    }
+
+    /// The entry at (i,j), which is 0 if it isn't stored
+    pub fn get(&self, i: usize, j: usize) -> f32 {
+        match self.position(i, j) {
+            Some(k) => self.values[k],
+            None => 0_f32,
+        }
+    }
+
+    pub(crate) fn get_values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub(crate) fn get_values_mut(&mut self) -> &mut [f32] {
+        &mut self.values
+    }
+
+    pub fn diagonal(&self) -> DVector<f32> {
+        DVector::from_iterator(self.nrows.min(self.ncols), (0..self.nrows.min(self.ncols)).map(|i| self.get(i, i)))
+    }
+
+    /// The matrix-vector product with `x`
+    pub fn mul_vector(&self, x: &DVector<f32>) -> DVector<f32> {
+        assert_eq!(x.len(), self.ncols, "Vector doesn't match the matrix");
+        DVector::from_iterator(self.nrows, (0..self.nrows).map(|i| {
+            let (columns, values) = self.row(i);
+            columns.iter().zip(values).map(|(j, v)| v * x[*j]).sum::<f32>()
+        }))
+    }
+
+    /// The matrix product with `other`
+    pub fn mul_matrix(&self, other: &CsrMatrix) -> CsrMatrix {
+        assert_eq!(self.ncols, other.nrows, "Matrices don't match for the product");
+        let mut triplets = Vec::new();
+        for i in 0..self.nrows {
+            let (columns, values) = self.row(i);
+            for (k, a) in columns.iter().zip(values) {
+                let (other_columns, other_values) = other.row(*k);
+                triplets.extend(other_columns.iter().zip(other_values).map(|(j, b)| (i, *j, a * b)));
+            }
+        }
+        CsrMatrix::from_triplets(self.nrows, other.ncols, &triplets)
+    }
+
+    /// The sum with `other`
+    pub fn add_matrix(&self, other: &CsrMatrix) -> CsrMatrix {
+        assert!(self.nrows == other.nrows && self.ncols == other.ncols, "Matrices don't match for the sum");
+        CsrMatrix::from_triplets(self.nrows, self.ncols, &[self.triplets(), other.triplets()].concat())
+    }
+
+    /// The stored entries as (row, column, value) triplets
+    pub fn triplets(&self) -> Vec<(usize, usize, f32)> {
+        (0..self.nrows)
+            .flat_map(|i| {
+                let (columns, values) = self.row(i);
+                columns.iter().zip(values).map(move |(j, v)| (i, *j, *v))
+            })
+            .collect()
+    }
+
+    /// Saves the matrix to `location` in the Matrix Market coordinate
+    /// format, with 1-based indices
+    pub fn save_matrix_market(&self, location: &str) -> std::io::Result<()> {
+        let mut file = File::create(location)?;
+        writeln!(file, "%%MatrixMarket matrix coordinate real general")?;
+        writeln!(file, "{} {} {}", self.nrows, self.ncols, self.get_nnz())?;
+        for (i, j, v) in self.triplets() {
+            writeln!(file, "{} {} {:e}", i + 1, j + 1, v)?;
+        }
+        Ok(())
+    }
+
+    pub fn transpose(&self) -> CsrMatrix {
+        let triplets: Vec<(usize, usize, f32)> = self.triplets().iter().map(|(i, j, v)| (*j, *i, *v)).collect();
+        CsrMatrix::from_triplets(self.ncols, self.nrows, &triplets)
+    }
+
+    pub fn to_dense(&self) -> DMatrix<f32> {
+        let mut dense = DMatrix::zeros(self.nrows, self.ncols);
+        for i in 0..self.nrows {
+            let (columns, values) = self.row(i);
+            for (j, v) in columns.iter().zip(values) {
+                dense[(i, *j)] = *v;
+            }
+        }
+        dense
+    }
+}
+
+#[test]
+fn test_csr_matrix() {
+    let a = CsrMatrix::from_triplets(3, 4, &[(2, 1, 1_f32), (0, 3, 2_f32), (0, 0, 4_f32), (2, 1, 0.5_f32), (1, 2, -1_f32)]);
+    assert_eq!(a.get_nnz(), 4);
+    assert_eq!(a.get(2, 1), 1.5);
+    assert_eq!(a.get(1, 1), 0_f32);
+    assert_eq!(a.row(0), (&[0_usize, 3][..], &[4_f32, 2_f32][..]));
+    assert_eq!(CsrMatrix::from_dense(&a.to_dense()), a);
+    assert_eq!(a.transpose().to_dense(), a.to_dense().transpose());
+
+    let x = DVector::from_vec(vec![1_f32, 2_f32, 3_f32, 4_f32]);
+    assert_eq!(a.mul_vector(&x), a.to_dense() * &x);
+    assert_eq!(a.diagonal(), DVector::from_vec(vec![4_f32, 0_f32, 0_f32]));
+    assert_eq!(a.mul_matrix(&a.transpose()).to_dense(), a.to_dense() * a.to_dense().transpose());
+    assert_eq!(a.add_matrix(&a).to_dense(), a.to_dense() * 2_f32);
+}